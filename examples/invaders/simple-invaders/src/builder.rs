@@ -0,0 +1,228 @@
+use crate::{
+    Mode, ScoreTable, World, BULLET_SPEED, LASER_CHANCE, LASER_SPEED, LIVES, MAX_HUD_HEIGHT,
+    MAX_PROJECTILE_SPEED, ONE_FRAME, ROWS,
+};
+use std::time::Duration;
+
+/// A builder to help create customized `World`s.
+#[derive(Debug)]
+pub struct WorldBuilder {
+    pub(crate) seed: (u64, u64),
+    pub(crate) debug: bool,
+    pub(crate) score_table: ScoreTable,
+    pub(crate) hud_height: usize,
+    pub(crate) bullet_speed: usize,
+    pub(crate) laser_speed: usize,
+    pub(crate) final_level: Option<u32>,
+    pub(crate) laser_chance: u32,
+    pub(crate) lives: u8,
+    pub(crate) invader_frame: Duration,
+    pub(crate) shields: bool,
+    pub(crate) deterministic: bool,
+    pub(crate) invader_hp: [u8; ROWS],
+    pub(crate) rewind: bool,
+}
+
+impl WorldBuilder {
+    /// Create a builder that can be finalized into a [`World`].
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Inputs for the pseudorandom number generator.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use simple_invaders::{ScoreTable, WorldBuilder};
+    ///
+    /// let world = WorldBuilder::new((1, 2))
+    ///     .score_table(ScoreTable {
+    ///         rows: [50, 40, 30, 20, 10],
+    ///         ..ScoreTable::default()
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn new(seed: (u64, u64)) -> Self {
+        Self {
+            seed,
+            debug: false,
+            score_table: ScoreTable::default(),
+            hud_height: 0,
+            bullet_speed: BULLET_SPEED,
+            laser_speed: LASER_SPEED,
+            final_level: None,
+            laser_chance: LASER_CHANCE,
+            lives: LIVES,
+            invader_frame: ONE_FRAME,
+            shields: true,
+            deterministic: false,
+            invader_hp: [1; ROWS],
+            rewind: false,
+        }
+    }
+
+    /// Enable or disable debug visualizations.
+    ///
+    /// Debug visualizations are disabled by default.
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Set the scoring rules.
+    ///
+    /// The default is [`ScoreTable::default`], which uses the arcade values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table has no UFO bonus options.
+    pub fn score_table(mut self, score_table: ScoreTable) -> Self {
+        assert!(!score_table.ufo_bonus.is_empty());
+
+        self.score_table = score_table;
+        self
+    }
+
+    /// Reserve a strip at the top of the screen for the HUD.
+    ///
+    /// The play area is drawn below the strip, offset by `hud_height` pixels, and is shortened by
+    /// the same amount so it still fits on the screen. The default is 0, which draws the HUD over
+    /// the top of the play area.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hud_height` is greater than 24, which would push the player off the screen.
+    pub fn hud_height(mut self, hud_height: usize) -> Self {
+        assert!(hud_height <= MAX_HUD_HEIGHT);
+
+        self.hud_height = hud_height;
+        self
+    }
+
+    /// Set the speed of the player's bullets, in pixels per second.
+    ///
+    /// The default is 240.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` is 0 or greater than 960. Faster bullets could pass through thin targets
+    /// without colliding.
+    pub fn bullet_speed(mut self, speed: usize) -> Self {
+        assert!(speed > 0 && speed <= MAX_PROJECTILE_SPEED);
+
+        self.bullet_speed = speed;
+        self
+    }
+
+    /// Set the speed of the invaders' lasers, in pixels per second.
+    ///
+    /// The default is 120.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` is 0 or greater than 960. Faster lasers could pass through thin targets
+    /// without colliding.
+    pub fn laser_speed(mut self, speed: usize) -> Self {
+        assert!(speed > 0 && speed <= MAX_PROJECTILE_SPEED);
+
+        self.laser_speed = speed;
+        self
+    }
+
+    /// Guarantee that the simulation is reproducible.
+    ///
+    /// All randomness in the world comes from the seeded PRNG, so two worlds with the same seed
+    /// and inputs always produce the same [`World::state_hash`]. Deterministic mode additionally
+    /// panics in debug builds when randomness could depend on wall-clock time, e.g. when calling
+    /// [`World::advance`], to catch accidental nondeterminism early. Disabled by default.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Keep a second of snapshots for [`World::rewind`].
+    ///
+    /// Each update stores a copy of the logical game state, so this is disabled by default.
+    pub fn rewind(mut self, rewind: bool) -> Self {
+        self.rewind = rewind;
+        self
+    }
+
+    /// Enable or disable the shields, for a harder game.
+    ///
+    /// Shields are enabled by default.
+    pub fn shields(mut self, shields: bool) -> Self {
+        self.shields = shields;
+        self
+    }
+
+    /// Choose a game mode preset.
+    ///
+    /// The preset replaces the number of lives, the invader speed, and
+    /// [`WorldBuilder::laser_chance`]. Other options set after this one take precedence.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        match mode {
+            Mode::Normal => {
+                self.lives = LIVES;
+                self.invader_frame = ONE_FRAME;
+                self.laser_chance = LASER_CHANCE;
+            }
+            Mode::SuddenDeath => {
+                self.lives = 1;
+                self.invader_frame = ONE_FRAME / 2;
+                self.laser_chance = LASER_CHANCE / 2;
+            }
+        }
+
+        self
+    }
+
+    /// Set how often the invaders fire lasers.
+    ///
+    /// Each time an invader in a full formation steps, there is a 1 in `chance` chance of firing a
+    /// laser. The formation reloads faster as it speeds up, so the chance improves as invaders
+    /// are destroyed. The default is 50.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chance` is 0.
+    pub fn laser_chance(mut self, chance: u32) -> Self {
+        assert!(chance > 0);
+
+        self.laser_chance = chance;
+        self
+    }
+
+    /// Set how many hits it takes to destroy an invader, for each formation row from top to bottom.
+    ///
+    /// Invaders that survive a hit flash for a moment. The default is 1 for every row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any row has 0 hit points.
+    pub fn invader_hp(mut self, hp: [u8; ROWS]) -> Self {
+        assert!(hp.iter().all(|&hp| hp > 0));
+
+        self.invader_hp = hp;
+        self
+    }
+
+    /// End the game with a win after clearing this level.
+    ///
+    /// By default, the game is endless.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level` is 0.
+    pub fn final_level(mut self, level: u32) -> Self {
+        assert!(level > 0);
+
+        self.final_level = Some(level);
+        self
+    }
+
+    /// Create the [`World`].
+    pub fn build(self) -> World {
+        World::from_builder(self)
+    }
+}
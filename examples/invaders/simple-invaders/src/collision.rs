@@ -1,18 +1,59 @@
 //! Collision detection primitives.
 
 use crate::geo::{Point, Rect};
-use crate::{Bullet, Invaders, Laser, Player, Shield, COLS, GRID, ROWS};
-use std::collections::HashSet;
+use crate::powerups::PowerUp;
+use crate::ufo::Ufo;
+use crate::{Bullet, Invader, Invaders, Laser, Player, Shield, COLS, FLASH_DURATION, GRID, ROWS};
+use std::collections::BTreeSet;
 
 /// Store information about collisions (for debug mode).
 #[derive(Debug, Default)]
 pub(crate) struct Collision {
-    pub(crate) bullet_details: HashSet<BulletDetail>,
-    pub(crate) laser_details: HashSet<LaserDetail>,
+    pub(crate) bullet_details: BTreeSet<BulletDetail>,
+    pub(crate) laser_details: BTreeSet<LaserDetail>,
+}
+
+/// The kinds of entities that take part in collision detection.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum CollisionLayer {
+    /// The player's tank.
+    Player,
+    /// A live invader.
+    Invader,
+    /// A shield.
+    Shield,
+    /// A bullet fired by the player.
+    Bullet,
+    /// A laser fired by an invader.
+    Laser,
+    /// A falling power-up.
+    PowerUp,
+    /// The bonus UFO.
+    Ufo,
+}
+
+/// The outcome of a bullet hitting an invader.
+#[derive(Debug)]
+pub(crate) enum InvaderHit {
+    /// The invader survived the hit.
+    Damaged,
+    /// The invader was destroyed, and removed from the fleet.
+    Destroyed(Invader),
+}
+
+/// The vertical direction that a projectile travels.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Heading {
+    /// Bullets travel up the screen.
+    Up,
+    /// Lasers travel down the screen.
+    Down,
 }
 
 /// Information regarding collisions between bullets and invaders, lasers, or shields.
-#[derive(Debug, Eq, Hash, PartialEq)]
+///
+/// Ordered, so that candidates are always resolved in the same order.
+#[derive(Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub(crate) enum BulletDetail {
     /// A grid position (col, row) for an invader.
     Invader(usize, usize),
@@ -20,10 +61,12 @@ pub(crate) enum BulletDetail {
     Shield(usize),
     /// Collided with a laser.
     Laser,
+    /// Collided with the UFO.
+    Ufo,
 }
 
 /// Information regarding collisions between lasers and shields or the player.
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub(crate) enum LaserDetail {
     /// A shield index.
     Shield(usize),
@@ -39,11 +82,14 @@ impl Collision {
     }
 
     /// Handle collisions between bullets and invaders.
+    ///
+    /// Invaders with more than one hit point survive the hit, and flash for a moment. Piercing
+    /// bullets survive the hit, and continue on to the next invader.
     pub(crate) fn bullet_to_invader(
         &mut self,
         bullet: &mut Option<Bullet>,
         invaders: &mut Invaders,
-    ) -> bool {
+    ) -> Option<InvaderHit> {
         // Broad phase collision detection
         let (top, right, bottom, left) = invaders.get_bounds();
         let invaders_rect = Rect::new(&Point::new(left, top), &Point::new(right, bottom));
@@ -70,25 +116,71 @@ impl Collision {
                 }
             }
 
-            // If any collision candidate is a hit, kill the bullet and invader
+            // If any collision candidate is a hit, kill the bullet and damage the invader
             for detail in self.bullet_details.iter() {
                 if let BulletDetail::Invader(x, y) = *detail {
-                    let invader = invaders.grid[y][x].as_ref().unwrap();
+                    // Candidates may have already been destroyed by another bullet
+                    let invader = match invaders.grid[y][x].as_mut() {
+                        Some(invader) => invader,
+                        None => continue,
+                    };
+                    let b = bullet.as_mut().unwrap();
+                    if b.pierced.contains(&(x, y)) {
+                        continue;
+                    }
                     let invader_rect = Rect::from_drawable(&invader.pos, &invader.sprite);
                     if bullet_rect.intersects(&invader_rect) {
-                        // TODO: Explosion! Score!
-                        invaders.grid[y][x] = None;
+                        // Destroy bullet, unless it pierces through
+                        if b.piercing {
+                            b.pierced.push((x, y));
+                        } else {
+                            *bullet = None;
+                        }
 
-                        // Destroy bullet
-                        *bullet = None;
+                        if invader.hp > 1 {
+                            invader.hp -= 1;
+                            invader.flash = FLASH_DURATION;
 
-                        return true;
+                            return Some(InvaderHit::Damaged);
+                        }
+
+                        // TODO: Explosion!
+                        let invader = invaders.grid[y][x].take().unwrap();
+
+                        return Some(InvaderHit::Destroyed(invader));
                     }
                 }
             }
         }
 
-        false
+        None
+    }
+
+    /// Handle collisions between bullets and the UFO.
+    pub(crate) fn bullet_to_ufo(
+        &mut self,
+        bullet: &mut Option<Bullet>,
+        ufo: &mut Option<Ufo>,
+    ) -> bool {
+        let hit = match (&bullet, &ufo) {
+            (Some(b), Some(u)) => {
+                let bullet_rect = Rect::from_drawable(&b.pos, &b.sprite);
+                let ufo_rect = Rect::from_drawable(&u.pos, &u.sprite);
+                bullet_rect.intersects(&ufo_rect)
+            }
+            _ => false,
+        };
+
+        if hit {
+            // TODO: Explosion!
+            self.bullet_details.insert(BulletDetail::Ufo);
+
+            // Destroy bullet and UFO
+            *bullet = None;
+            *ufo = None;
+        }
+
+        hit
     }
 
     /// Handle collisions between bullets and shields.
@@ -103,15 +195,18 @@ impl Collision {
             for (i, shield_rect) in shield_rects.iter().enumerate() {
                 // broad phase collision detection
                 if bullet_rect.intersects(shield_rect) {
-                    // TODO: Narrow phase (per-pixel) collision detection
-                    // TODO: Break shield
-
-                    // TODO: Explosion!
                     let detail = BulletDetail::Shield(i);
                     self.bullet_details.insert(detail);
 
-                    // Destroy bullet
-                    *bullet = None;
+                    // Narrow phase (per-pixel) collision detection
+                    if let Some(impact) = shield_impact(&bullet_rect, &shields[i], Heading::Up) {
+                        // TODO: Explosion!
+                        shields[i].damage(&impact, Heading::Up);
+
+                        // Destroy bullet
+                        *bullet = None;
+                        break;
+                    }
                 }
             }
         }
@@ -130,29 +225,32 @@ impl Collision {
     }
 
     /// Handle collisions between lasers and bullets.
-    pub(crate) fn laser_to_bullet(&mut self, laser: &Laser, bullet: &mut Option<Bullet>) -> bool {
-        let mut destroy = false;
-        if bullet.is_some() {
-            let laser_rect = Rect::from_drawable(&laser.pos, &laser.sprite);
+    pub(crate) fn laser_to_bullet(&mut self, laser: &Laser, bullets: &mut Vec<Bullet>) -> bool {
+        let laser_rect = Rect::from_drawable(&laser.pos, &laser.sprite);
 
-            if let Some(bullet) = &bullet {
-                let bullet_rect = Rect::from_drawable(&bullet.pos, &bullet.sprite);
-                if bullet_rect.intersects(&laser_rect) {
-                    // TODO: Explosion!
-                    let detail = BulletDetail::Laser;
-                    self.bullet_details.insert(detail);
+        let hit = bullets.iter().position(|bullet| {
+            let bullet_rect = Rect::from_drawable(&bullet.pos, &bullet.sprite);
+            bullet_rect.intersects(&laser_rect)
+        });
 
-                    // Destroy laser and bullet
-                    destroy = true;
-                }
-            }
+        if let Some(i) = hit {
+            // TODO: Explosion!
+            let detail = BulletDetail::Laser;
+            self.bullet_details.insert(detail);
 
-            if destroy {
-                *bullet = None;
-            }
+            // Destroy laser and bullet
+            bullets.remove(i);
         }
 
-        destroy
+        hit.is_some()
+    }
+
+    /// Handle collisions between power-ups and the player.
+    pub(crate) fn powerup_to_player(&mut self, powerup: &PowerUp, player: &Player) -> bool {
+        let powerup_rect = Rect::from_drawable(&powerup.pos, &powerup.sprite);
+        let player_rect = Rect::from_drawable(&player.pos, &player.sprite);
+
+        powerup_rect.intersects(&player_rect)
     }
 
     /// Handle collisions between lasers and shields.
@@ -164,15 +262,18 @@ impl Collision {
         for (i, shield_rect) in shield_rects.iter().enumerate() {
             // broad phase collision detection
             if laser_rect.intersects(shield_rect) {
-                // TODO: Narrow phase (per-pixel) collision detection
-                // TODO: Break shield
-
-                // TODO: Explosion!
                 let detail = LaserDetail::Shield(i);
                 self.laser_details.insert(detail);
 
-                // Destroy laser
-                destroy = true;
+                // Narrow phase (per-pixel) collision detection
+                if let Some(impact) = shield_impact(&laser_rect, &shields[i], Heading::Down) {
+                    // TODO: Explosion!
+                    shields[i].damage(&impact, Heading::Down);
+
+                    // Destroy laser
+                    destroy = true;
+                    break;
+                }
             }
         }
 
@@ -180,11 +281,40 @@ impl Collision {
     }
 }
 
-fn create_shield_rects(shields: &[Shield]) -> [Rect; 4] {
-    [
-        Rect::from_drawable(&shields[0].pos, &shields[0].sprite),
-        Rect::from_drawable(&shields[1].pos, &shields[1].sprite),
-        Rect::from_drawable(&shields[2].pos, &shields[2].sprite),
-        Rect::from_drawable(&shields[3].pos, &shields[3].sprite),
-    ]
+fn create_shield_rects(shields: &[Shield]) -> Vec<Rect> {
+    shields
+        .iter()
+        .map(|shield| Rect::from_drawable(&shield.pos, &shield.sprite))
+        .collect()
+}
+
+/// Find the first shield pixel that overlaps a projectile, in its direction of travel.
+///
+/// # Returns
+///
+/// The impact point in shield-local coordinates, if any.
+fn shield_impact(rect: &Rect, shield: &Shield, heading: Heading) -> Option<Point> {
+    let shield_rect = Rect::from_drawable(&shield.pos, &shield.sprite);
+    let top = rect.p1.y.max(shield_rect.p1.y);
+    let bottom = rect.p2.y.min(shield_rect.p2.y);
+    let left = rect.p1.x.max(shield_rect.p1.x);
+    let right = rect.p2.x.min(shield_rect.p2.x);
+
+    for y in top..bottom {
+        // Projectiles moving up the screen hit the bottom of the shield first
+        let y = match heading {
+            Heading::Up => top + bottom - 1 - y,
+            Heading::Down => y,
+        };
+
+        for x in left..right {
+            let x = x - shield.pos.x;
+            let y = y - shield.pos.y;
+            if shield.sprite.is_lit(x, y) {
+                return Some(Point::new(x, y));
+            }
+        }
+    }
+
+    None
 }
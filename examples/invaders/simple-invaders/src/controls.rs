@@ -1,16 +1,43 @@
 /// Player control inputs.
-#[derive(Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub struct Controls {
     /// Move the player.
     pub direction: Direction,
     /// Shoot the cannon.
     pub fire: bool,
+    /// Pause or resume the game. Only a fresh press toggles the pause, holding it does nothing.
+    pub pause: bool,
+}
+
+/// The buttons that can be pressed or held.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Action {
+    /// The fire button.
+    Fire,
+    /// The pause button.
+    Pause,
+}
+
+impl Controls {
+    /// Test whether the button for an action is down.
+    pub(crate) fn held(&self, action: Action) -> bool {
+        match action {
+            Action::Fire => self.fire,
+            Action::Pause => self.pause,
+        }
+    }
+
+    /// Test whether the button for an action is down, but was up on the previous frame.
+    pub(crate) fn pressed(&self, prev: &Controls, action: Action) -> bool {
+        self.held(action) && !prev.held(action)
+    }
 }
 
 /// The player can only move left or right, but can also be stationary.
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub enum Direction {
     /// Do not move the player.
+    #[default]
     Still,
     /// Move to the left.
     Left,
@@ -18,8 +45,23 @@ pub enum Direction {
     Right,
 }
 
-impl Default for Direction {
-    fn default() -> Direction {
-        Direction::Still
+impl Direction {
+    /// Swap left and right.
+    pub(crate) fn mirrored(self) -> Direction {
+        match self {
+            Direction::Still => Direction::Still,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
     }
 }
+
+/// How the fire button shoots the cannon.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum FireMode {
+    /// Shoot as soon as fire is pressed.
+    #[default]
+    Tap,
+    /// Shoot when fire is released. Holding fire long enough charges a piercing shot.
+    Hold,
+}
@@ -48,9 +48,9 @@ pub(crate) fn draw_invaders(screen: &mut [u8], invaders: &Invaders, collision: &
     }
 }
 
-/// Draw bounding box for bullet.
-pub(crate) fn draw_bullet(screen: &mut [u8], bullet: Option<&Bullet>) {
-    if let Some(bullet) = bullet {
+/// Draw bounding boxes for bullets.
+pub(crate) fn draw_bullets(screen: &mut [u8], bullets: &[Bullet]) {
+    for bullet in bullets {
         let p1 = bullet.pos;
         let p2 = p1 + Point::new(bullet.sprite.width(), bullet.sprite.height());
 
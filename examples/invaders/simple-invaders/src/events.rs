@@ -0,0 +1,36 @@
+use crate::PowerUpKind;
+
+/// Notable things that happened during a single call to [`World::update`](crate::World::update).
+///
+/// Events are recorded in the order they were resolved. Within a single update, player input is
+/// always resolved before the invaders act, so a bullet fired on the same frame that a laser is
+/// spawned is reported first. This order is part of the public contract and keeps replays
+/// deterministic.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// The player fired a bullet.
+    BulletFired,
+    /// An invader fired a laser.
+    LaserFired,
+    /// An invader was hit by the player, but survived.
+    InvaderDamaged,
+    /// An invader was destroyed by the player.
+    InvaderKilled,
+    /// The player was hit by a laser.
+    PlayerHit,
+    /// The player was hit by a laser, but was only knocked back. See
+    /// [`World::set_knockback`](crate::World::set_knockback).
+    PlayerKnockedBack,
+    /// The entire fleet was destroyed, advancing to the next level.
+    LevelCleared,
+    /// The wave was cleared without losing a life or taking shield damage, awarding a bonus.
+    PerfectWave,
+    /// The UFO appeared.
+    UfoSpawned,
+    /// The UFO was destroyed by the player, awarding a bonus.
+    UfoDestroyed,
+    /// The player collected a power-up.
+    PowerUpCollected(PowerUpKind),
+    /// The score reached a milestone, awarding an extra life.
+    ExtraLife(u32),
+}
@@ -3,17 +3,23 @@
 use crate::sprites::Drawable;
 
 /// A tiny position vector.
-#[derive(Copy, Clone, Debug, Default)]
-pub(crate) struct Point {
-    pub(crate) x: usize,
-    pub(crate) y: usize,
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Point {
+    /// Horizontal position in pixels.
+    pub x: usize,
+    /// Vertical position in pixels.
+    pub y: usize,
 }
 
 /// A tiny rectangle based on two absolute `Point`s.
-#[derive(Copy, Clone, Debug, Default)]
-pub(crate) struct Rect {
-    pub(crate) p1: Point,
-    pub(crate) p2: Point,
+///
+/// `p1` is the top-left corner (inclusive) and `p2` is the bottom-right corner (exclusive).
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Rect {
+    /// Top-left corner.
+    pub p1: Point,
+    /// Bottom-right corner.
+    pub p2: Point,
 }
 
 impl Point {
@@ -7,20 +7,49 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-use crate::collision::Collision;
-pub use crate::controls::{Controls, Direction};
-use crate::geo::Point;
+pub use crate::builder::WorldBuilder;
+pub use crate::collision::CollisionLayer;
+use crate::collision::{Collision, Heading, InvaderHit};
+use crate::controls::Action;
+pub use crate::controls::{Controls, Direction, FireMode};
+pub use crate::events::Event;
+pub use crate::geo::{Point, Rect};
 use crate::loader::{load_assets, Assets};
-use crate::sprites::{blit, Animation, Drawable, Frame, Sprite, SpriteRef};
+pub use crate::mode::Mode;
+pub use crate::palette::{level_palette, Palette};
+use crate::powerups::PowerUp;
+pub use crate::powerups::PowerUpKind;
+pub use crate::render::{DrawCommand, Graphic, Layer};
+pub use crate::score::{ScoreOverflow, ScoreTable};
+use crate::snapshot::Snapshot;
+pub use crate::sprites::InvaderKind;
+use crate::sprites::{mirror, Animation, Drawable, Frame, Sprite, SpriteRef};
+pub use crate::state::{GameState, GameStats, Outcome};
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::ufo::Ufo;
 use randomize::PCG32;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
 use std::time::Duration;
 
+mod builder;
 mod collision;
 mod controls;
 mod debug;
+mod events;
 mod geo;
 mod loader;
+mod mode;
+mod palette;
+mod powerups;
+mod render;
+mod score;
+mod snapshot;
 mod sprites;
+mod state;
+mod text;
+mod ufo;
 
 /// The screen width is constant (units are in pixels)
 pub const WIDTH: usize = 224;
@@ -46,44 +75,212 @@ const PLAYER_START: Point = Point::new(80, 216);
 const LASER_OFFSET: Point = Point::new(4, 10);
 const BULLET_OFFSET: Point = Point::new(7, 0);
 
+// Projectile speeds in pixels per second
+const BULLET_SPEED: usize = 240;
+const LASER_SPEED: usize = 120;
+// Projectiles may not move further than the height of a bullet in one time step, or they could
+// tunnel through thin targets
+const MAX_PROJECTILE_SPEED: usize = 4 * FPS;
+
+// The UFO appears with a 1:6000 chance each time step (about once every 25 seconds)
+const UFO_CHANCE: u32 = 6000;
+const UFO_Y: usize = 32;
+
+// Power-ups are dropped by destroyed invaders with a 1:16 chance
+const POWERUP_CHANCE: u32 = 16;
+
+// The burst power-up fires a volley of bullets spaced vertically
+const BURST_BULLETS: usize = 3;
+const BURST_SPACING: usize = 8;
+
+// Invaders flash for a moment when they survive a hit. Tints multiply each color channel, so
+// the flash is white only because the invader sprites are white.
+const FLASH_DURATION: Duration = Duration::from_millis(100);
+const FLASH_COLOR: [u8; 4] = [255, 255, 255, 255];
+
+// Holding fire this long in hold mode charges a piercing shot
+const CHARGE_THRESHOLD: Duration = Duration::from_millis(500);
+
+// Invaders bob up and down by a pixel when enabled, in a wave across the columns
+const BOB_STEP: Duration = Duration::from_millis(250);
+const BOB_OFFSETS: [isize; 4] = [0, 1, 0, -1];
+
+// A full formation fires lasers with a 1:50 chance each time an invader steps. The chance
+// improves as the formation speeds up, down to 1:4 for the last invader.
+const LASER_CHANCE: u32 = 50;
+const MIN_LASER_CHANCE: u32 = 4;
+
+// Invaders hold their fire for a moment at the start of each wave
+const GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+// Destroyed invaders flash for a moment as they die, when enabled, blinking on and off
+const GHOST_DURATION: Duration = Duration::from_millis(150);
+const GHOST_BLINK: Duration = Duration::from_millis(50);
+
+// Analog stick movement smaller than this is ignored
+const AXIS_DEADZONE: f32 = 0.25;
+
+// Snapshots are kept for rewinding up to one second
+const REWIND_FRAMES: usize = FPS;
+
+// Classic mode tints the invaders in two color bands, like the arcade cabinet's overlay
+const BAND_COLORS: [[u8; 4]; ROWS] = [
+    [255, 64, 160, 255],
+    [255, 64, 160, 255],
+    [64, 255, 96, 255],
+    [64, 255, 96, 255],
+    [64, 255, 96, 255],
+];
+
+// World::advance runs at most this many fixed time steps per call by default (a quarter second)
+const MAX_FRAMES_PER_UPDATE: usize = FPS / 4;
+
+// The last invader standing moves much faster, with a random stride
+const FINALE_STEPS: [usize; 3] = [4, 6, 8];
+
+// In the knockback variant, the first hit on each life pushes the player aside and stuns them
+const KNOCKBACK: usize = 12;
+const STUN_DURATION: Duration = Duration::from_millis(500);
+
+// Invaders destroyed within this long of each other build a combo
+const COMBO_WINDOW: Duration = Duration::from_secs(1);
+
+// The optional ground line across the bottom of the play area
+const BASELINE_COLOR: [u8; 4] = [32, 255, 32, 255];
+
+// The static PRNG seed for `World::default`
+const DEFAULT_SEED: (u64, u64) = (6_364_136_223_846_793_005, 1);
+
+// Player starts with this many lives
+const LIVES: u8 = 3;
+
+// Wave countdown positioning
+const COUNTDOWN_Y: usize = 168;
+
+// A red border pulses around the play area when the player is on their last life
+const WARNING_PULSE: Duration = Duration::from_millis(250);
+const WARNING_COLOR: [u8; 4] = [255, 0, 0, 255];
+
+// HUD positioning and color
+const HUD_HEIGHT: usize = 16;
+const MAX_HUD_HEIGHT: usize = 24;
+const HUD_MARGIN: usize = 4;
+const HUD_COLOR: [u8; 4] = [255, 255, 255, 255];
+
 #[derive(Debug)]
 pub struct World {
     invaders: Invaders,
     lasers: Vec<Laser>,
     shields: Vec<Shield>,
     player: Player,
-    bullet: Option<Bullet>,
+    bullets: Vec<Bullet>,
+    powerups: Vec<PowerUp>,
+    ufo: Option<Ufo>,
+    ufo_from_left: bool,
     collision: Collision,
-    _score: u32,
+    events: Vec<Event>,
+    score_table: ScoreTable,
+    score: u32,
+    high_score: u32,
+    lives: u8,
+    warning_dt: Duration,
+    level: u32,
+    damage_taken_this_wave: bool,
+    palette: Palette,
     assets: Assets,
     dt: Duration,
-    gameover: bool,
+    wave_countdown: Duration,
+    countdown: Duration,
+    grace: Duration,
+    laser_chance: u32,
+    state: GameState,
+    outcome: Option<Outcome>,
+    final_level: Option<u32>,
     prng: PCG32,
+    // UFOs and power-ups roll on their own stream, so they never shift the timing of invader fire
+    bonus_prng: PCG32,
     debug: bool,
+    mirror: bool,
+    hud_height: usize,
+    fire_mode: FireMode,
+    charge_timer: Duration,
+    bob: bool,
+    bob_dt: Duration,
+    bullet_speed: usize,
+    laser_speed: usize,
+    ghosts: Vec<Ghost>,
+    death_flash: bool,
+    snapshots: VecDeque<Snapshot>,
+    banded: bool,
+    idle_time: Duration,
+    accumulator: Duration,
+    max_frames_per_update: usize,
+    invader_frame: Duration,
+    bullet_color: Option<[u8; 4]>,
+    laser_color: Option<[u8; 4]>,
+    deterministic: bool,
+    // Set while running a fixed time step, the only time randomness is allowed in deterministic
+    // mode
+    stepping: bool,
+    knockback: bool,
+    knockback_ready: bool,
+    stun_timer: Duration,
+    baseline: Option<usize>,
+    prev_controls: Controls,
+    paused: bool,
+    score_cap: Option<u64>,
+    score_overflow: ScoreOverflow,
+    // Every point scored, ignoring the score cap
+    points: u64,
+    combo: u32,
+    combo_timer: Duration,
+    combo_bullet_color: Option<[u8; 4]>,
+    invader_hp: [u8; ROWS],
+    rewind: bool,
 }
 
 /// A fleet of invaders.
-#[derive(Debug)]
+#[derive(Clone, Debug, Hash)]
 struct Invaders {
     grid: Vec<Vec<Option<Invader>>>,
     stepper: Point,
     direction: Direction,
     descend: bool,
+    phase: bool,
     bounds: Bounds,
 }
 
 /// Everything you ever wanted to know about Invaders.
-#[derive(Debug)]
+#[derive(Clone, Debug, Hash)]
 struct Invader {
     sprite: SpriteRef,
     pos: Point,
-    _score: u32,
+    score: u32,
+    hp: u8,
+    flash: Duration,
+}
+
+/// A destroyed invader, drawn for a moment as it dies.
+#[derive(Clone, Debug, Hash)]
+struct Ghost {
+    sprite: SpriteRef,
+    pos: Point,
+    ttl: Duration,
+}
+
+impl Ghost {
+    /// Test whether the blinking ghost is currently visible.
+    fn visible(&self) -> bool {
+        let elapsed = GHOST_DURATION.saturating_sub(self.ttl);
+
+        (elapsed.as_nanos() / GHOST_BLINK.as_nanos()) & 1 == 0
+    }
 }
 
 /// Creates a boundary around the live invaders.
 ///
 /// Used for collision detection and minor optimizations.
-#[derive(Debug)]
+#[derive(Clone, Debug, Hash)]
 struct Bounds {
     pos: Point,
     left_col: usize,
@@ -93,7 +290,7 @@ struct Bounds {
 }
 
 /// The player entity.
-#[derive(Debug)]
+#[derive(Clone, Debug, Hash)]
 struct Player {
     sprite: SpriteRef,
     pos: Point,
@@ -101,27 +298,34 @@ struct Player {
 }
 
 /// The shield entity.
-#[derive(Debug)]
+#[derive(Clone, Debug, Hash)]
 struct Shield {
     // Shield sprite is not referenced because we want to deform it when it gets shot
     sprite: Sprite,
+    // The undamaged pixels, for comparison with the deformed sprite
+    pristine: Rc<[u8]>,
     pos: Point,
 }
 
 /// The laser entity.
-#[derive(Debug)]
+#[derive(Clone, Debug, Hash)]
 struct Laser {
     sprite: SpriteRef,
     pos: Point,
-    dt: Duration,
+    travel: usize,
 }
 
 /// The cannon entity.
-#[derive(Debug)]
+#[derive(Clone, Debug, Hash)]
 struct Bullet {
     sprite: SpriteRef,
     pos: Point,
-    dt: Duration,
+    travel: usize,
+    // Piercing bullets pass through invaders, damaging each one only once
+    piercing: bool,
+    pierced: Vec<(usize, usize)>,
+    // Fired while a combo was active
+    combo: bool,
 }
 
 trait DeltaTime {
@@ -142,16 +346,15 @@ impl DeltaTime for Player {
     }
 }
 
-impl DeltaTime for Laser {
-    fn update(&mut self) -> usize {
-        Self::update_dt(&mut self.dt, ONE_FRAME)
-    }
-}
+/// Move a projectile at the given speed (in pixels per second) for one time step.
+///
+/// Sub-pixel movement is accumulated in `travel` between time steps.
+fn projectile_step(travel: &mut usize, speed: usize) -> usize {
+    *travel += speed;
+    let pixels = *travel / FPS;
+    *travel %= FPS;
 
-impl DeltaTime for Bullet {
-    fn update(&mut self) -> usize {
-        Self::update_dt(&mut self.dt, TIME_STEP)
-    }
+    pixels
 }
 
 impl World {
@@ -179,125 +382,373 @@ impl World {
     ///
     /// let world = World::new(seed, false);
     /// ```
+    ///
+    /// See [`WorldBuilder`] for more options.
     pub fn new(seed: (u64, u64), debug: bool) -> World {
+        WorldBuilder::new(seed).debug(debug).build()
+    }
+
+    /// Create a `World` for the daily challenge.
+    ///
+    /// The PRNG seed is derived from the date, so everyone playing on the same day faces the same
+    /// sequence of lasers, UFOs, and power-ups.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - The `(year, month, day)` of the challenge.
+    pub fn daily(date: (u16, u8, u8)) -> World {
+        let (year, month, day) = date;
+        let days = u64::from(year) << 16 | u64::from(month) << 8 | u64::from(day);
+
+        World::new((splitmix64(days), splitmix64(!days)), false)
+    }
+
+    /// Convert analog gamepad inputs into player controls.
+    ///
+    /// # Arguments
+    ///
+    /// * `x_axis` - Horizontal stick position, from -1.0 (left) to 1.0 (right). Small movements
+    ///   within the deadzone around the center are ignored.
+    /// * `fire` - Shoot the cannon.
+    pub fn controls_from_axes(x_axis: f32, fire: bool) -> Controls {
+        let direction = if x_axis <= -AXIS_DEADZONE {
+            Direction::Left
+        } else if x_axis >= AXIS_DEADZONE {
+            Direction::Right
+        } else {
+            Direction::Still
+        };
+
+        Controls {
+            direction,
+            fire,
+            ..Controls::default()
+        }
+    }
+
+    /// Create a `World` from a finalized builder.
+    fn from_builder(builder: WorldBuilder) -> World {
         use Frame::*;
 
+        let WorldBuilder {
+            seed,
+            debug,
+            score_table,
+            hud_height,
+            bullet_speed,
+            laser_speed,
+            final_level,
+            laser_chance,
+            lives,
+            invader_frame,
+            shields,
+            deterministic,
+            invader_hp,
+            rewind,
+        } = builder;
+
         // Load assets first
         let assets = load_assets();
 
-        // TODO: Create invaders one-at-a-time
-        let invaders = Invaders {
-            grid: make_invader_grid(&assets),
-            stepper: Point::new(COLS - 1, 0),
-            direction: Direction::Right,
-            descend: false,
-            bounds: Bounds::default(),
-        };
+        let invaders = Invaders::new(&assets, &score_table, &invader_hp);
         let lasers = Vec::new();
-        let shields = (0..4)
-            .map(|i| Shield {
-                sprite: Sprite::new(&assets, Shield1),
-                pos: Point::new(i * 45 + 32, 192),
-            })
-            .collect();
+        let shields = if shields {
+            (0..4)
+                .map(|i| Shield::new(&assets, Point::new(i * 45 + 32, 192)))
+                .collect()
+        } else {
+            Vec::new()
+        };
         let player = Player {
             sprite: SpriteRef::new(&assets, Player1, Duration::from_millis(100)),
             pos: PLAYER_START,
             dt: Duration::default(),
         };
-        let bullet = None;
+        let bullets = Vec::new();
+        let powerups = Vec::new();
+        let ufo = None;
         let collision = Collision::default();
-        let _score = 0;
+        let events = Vec::new();
+        let score = 0;
+        let high_score = 0;
+
+        let level = 1;
+        let palette = level_palette(level);
 
         let dt = Duration::default();
-        let gameover = false;
+        let state = GameState::Playing;
         let prng = PCG32::seed(seed.0, seed.1);
+        let bonus_prng = PCG32::seed(seed.0, !seed.1);
 
         World {
             invaders,
             lasers,
             shields,
             player,
-            bullet,
+            bullets,
+            powerups,
+            ufo,
+            ufo_from_left: true,
             collision,
-            _score,
+            events,
+            score_table,
+            score,
+            high_score,
+            lives,
+            warning_dt: Duration::default(),
+            level,
+            damage_taken_this_wave: false,
+            palette,
             assets,
             dt,
-            gameover,
+            wave_countdown: Duration::default(),
+            countdown: Duration::default(),
+            grace: GRACE_PERIOD,
+            laser_chance,
+            state,
+            outcome: None,
+            final_level,
             prng,
+            bonus_prng,
             debug,
+            mirror: false,
+            hud_height,
+            fire_mode: FireMode::default(),
+            charge_timer: Duration::default(),
+            bob: false,
+            bob_dt: Duration::default(),
+            bullet_speed,
+            laser_speed,
+            ghosts: Vec::new(),
+            death_flash: false,
+            snapshots: VecDeque::new(),
+            banded: false,
+            idle_time: Duration::default(),
+            accumulator: Duration::default(),
+            max_frames_per_update: MAX_FRAMES_PER_UPDATE,
+            invader_frame,
+            bullet_color: None,
+            laser_color: None,
+            deterministic,
+            stepping: false,
+            knockback: false,
+            knockback_ready: true,
+            stun_timer: Duration::default(),
+            baseline: None,
+            prev_controls: Controls::default(),
+            paused: false,
+            score_cap: None,
+            score_overflow: ScoreOverflow::Rollover,
+            points: 0,
+            combo: 0,
+            combo_timer: Duration::default(),
+            combo_bullet_color: None,
+            invader_hp,
+            rewind,
+        }
+    }
+
+    /// Remove invaders from the formation, e.g. when resuming a saved game.
+    ///
+    /// The fleet boundary is recomputed to fit the remaining invaders. Clearing the entire
+    /// formation clears the wave as if the last invader was shot: the wave bonus (and the perfect
+    /// bonus, if no damage was taken) is added to the score, and the game advances to the next
+    /// level, or is won on the final level.
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - Grid positions as `(col, row)` tuples. Cells that are already empty are
+    ///   ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any cell is outside of the invader grid.
+    pub fn with_cleared_cells(&mut self, cells: &[(usize, usize)]) {
+        for &(col, row) in cells {
+            assert!(col < COLS && row < ROWS, "Cell is outside of the grid");
+
+            self.invaders.grid[row][col] = None;
+        }
+
+        if self.invaders.shrink_bounds() {
+            self.next_level();
+        }
+    }
+
+    /// The deformation of each shield as a compact bit mask, in order from left to right.
+    ///
+    /// This is useful for saving a game in progress. See [`World::with_shield_damage`].
+    pub fn shield_damage(&self) -> Vec<Vec<u8>> {
+        self.shields.iter().map(Shield::damage_mask).collect()
+    }
+
+    /// Deform the shields, e.g. when resuming a saved game.
+    ///
+    /// # Arguments
+    ///
+    /// * `masks` - Masks previously returned by [`World::shield_damage`]. Damage is added to
+    ///   any that the shields have already taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of masks does not match the number of shields, or if any mask is not
+    /// the expected size.
+    pub fn with_shield_damage(&mut self, masks: &[Vec<u8>]) {
+        assert_eq!(
+            masks.len(),
+            self.shields.len(),
+            "Wrong number of shield masks"
+        );
+
+        for (shield, mask) in self.shields.iter_mut().zip(masks) {
+            shield.apply_damage_mask(mask);
         }
     }
 
     /// Update the internal state.
     ///
+    /// The player inputs are always resolved before the invaders are stepped. See [`Event`] for
+    /// details on how this affects the order of events.
+    ///
     /// # Arguments
     ///
-    /// * `dt`: The time delta since last update.
     /// * `controls`: The player inputs.
     pub fn update(&mut self, controls: &Controls) {
-        if self.gameover {
+        self.stepping = true;
+        self.step(controls);
+        self.stepping = false;
+    }
+
+    /// Run a single fixed time step for [`World::update`].
+    fn step(&mut self, controls: &Controls) {
+        // Clear the events from the last update
+        self.events.clear();
+
+        // Remember the state before this update, for rewinding
+        if self.rewind {
+            if self.snapshots.len() == REWIND_FRAMES {
+                self.snapshots.pop_front();
+            }
+            self.snapshots.push_back(Snapshot::new(self));
+        }
+
+        self.apply_controls_edge(controls);
+        if self.paused {
+            return;
+        }
+
+        // Track how long the player has been idle, even when the game is not being played
+        if controls.direction != Direction::Still || controls.fire {
+            self.idle_time = Duration::default();
+        } else {
+            self.idle_time += TIME_STEP;
+        }
+
+        if self.state == GameState::GameOver {
             // TODO: Add a game over screen
             return;
         }
 
+        // The world is frozen while counting down to the start of a wave
+        if self.countdown > Duration::default() {
+            self.countdown = self.countdown.saturating_sub(TIME_STEP);
+            return;
+        }
+
         // Advance the timer by the delta time
         self.dt += TIME_STEP;
+        self.grace = self.grace.saturating_sub(TIME_STEP);
+        self.combo_timer = self.combo_timer.saturating_sub(TIME_STEP);
+        if self.combo_timer == Duration::default() {
+            self.combo = 0;
+        }
 
         // Clear the collision details
         self.collision.clear();
 
-        // Step the invaders one by one
-        while self.dt >= ONE_FRAME {
-            self.dt -= ONE_FRAME;
-            self.step_invaders();
-        }
-
         // Handle player movement and animation
         self.step_player(controls);
 
-        if let Some(bullet) = &mut self.bullet {
-            // Handle bullet movement
-            let velocity = bullet.update();
+        // Pulse the warning indicator while the player is on their last life
+        if self.lives == 1 {
+            self.warning_dt += TIME_STEP;
+        } else {
+            self.warning_dt = Duration::default();
+        }
 
-            if bullet.pos.y > velocity {
-                bullet.pos.y -= velocity;
-                bullet.sprite.animate(&self.assets);
+        // Advance the bob animation
+        if self.bob {
+            self.bob_dt += TIME_STEP;
+        }
 
-                // Handle collisions
-                if self
-                    .collision
-                    .bullet_to_invader(&mut self.bullet, &mut self.invaders)
-                {
-                    // One of the end scenarios
-                    self.gameover = self.invaders.shrink_bounds();
-                } else {
-                    self.collision
-                        .bullet_to_shield(&mut self.bullet, &mut self.shields);
-                }
-            } else {
-                self.bullet = None;
-            }
+        // Fade out invader hit flashes
+        for invader in self.invaders.grid.iter_mut().flatten().flatten() {
+            invader.flash = invader.flash.saturating_sub(TIME_STEP);
+        }
+
+        // Fade out dying invaders
+        for ghost in self.ghosts.iter_mut() {
+            ghost.ttl = ghost.ttl.saturating_sub(TIME_STEP);
+        }
+        self.ghosts.retain(|ghost| ghost.ttl > Duration::default());
+
+        // Step the invaders one by one
+        while self.dt >= self.invader_frame {
+            self.dt -= self.invader_frame;
+            self.step_invaders();
+        }
+
+        // Handle UFO movement
+        self.step_ufo();
+
+        // Handle bullet movement
+        for bullet in std::mem::take(&mut self.bullets) {
+            let mut bullet = Some(bullet);
+            self.step_bullet(&mut bullet);
+            self.bullets.extend(bullet);
         }
 
         // Handle laser movement
+        let floor = self.floor();
         let mut destroy = Vec::new();
         for (i, laser) in self.lasers.iter_mut().enumerate() {
-            let velocity = laser.update() * 2;
+            let velocity = projectile_step(&mut laser.travel, self.laser_speed);
 
-            if laser.pos.y < self.player.pos.y {
+            if laser.pos.y < floor {
                 laser.pos.y += velocity;
                 laser.sprite.animate(&self.assets);
 
                 // Handle collisions
                 if self.collision.laser_to_player(laser, &self.player) {
-                    // One of the end scenarios
-                    self.gameover = true;
+                    if self.knockback && self.knockback_ready {
+                        // Knock the player away from the laser, and stun them for a moment
+                        let width = self.player.sprite.width();
+                        let center = self.player.pos.x + width / 2;
+                        self.player.pos.x = if laser.pos.x < center {
+                            (self.player.pos.x + KNOCKBACK).min(WIDTH - width * 2)
+                        } else {
+                            self.player.pos.x.saturating_sub(KNOCKBACK).max(width)
+                        };
+                        self.knockback_ready = false;
+                        self.stun_timer = STUN_DURATION;
+                        self.events.push(Event::PlayerKnockedBack);
+                    } else {
+                        // One of the end scenarios
+                        self.lives = self.lives.saturating_sub(1);
+                        self.knockback_ready = true;
+                        if self.lives == 0 {
+                            self.state = GameState::GameOver;
+                            self.outcome = Some(Outcome::LostAllLives);
+                        }
+                        self.events.push(Event::PlayerHit);
+                    }
+                    self.damage_taken_this_wave = true;
 
                     destroy.push(i);
-                } else if self.collision.laser_to_bullet(laser, &mut self.bullet)
-                    || self.collision.laser_to_shield(laser, &mut self.shields)
-                {
+                } else if self.collision.laser_to_bullet(laser, &mut self.bullets) {
+                    destroy.push(i);
+                } else if self.collision.laser_to_shield(laser, &mut self.shields) {
+                    self.damage_taken_this_wave = true;
                     destroy.push(i);
                 }
             } else {
@@ -309,339 +760,3390 @@ impl World {
         for &i in destroy.iter().rev() {
             self.lasers.remove(i);
         }
-    }
 
-    /// Draw the internal state to the screen.
-    ///
-    /// Calling this method more than once without an `update` call between is a no-op.
-    pub fn draw(&mut self, screen: &mut [u8]) {
-        // Clear the screen
-        clear(screen);
+        // Handle power-up movement
+        let mut collected = Vec::new();
+        let mut destroy = Vec::new();
+        for (i, powerup) in self.powerups.iter_mut().enumerate() {
+            let velocity = powerup.update();
+
+            if powerup.pos.y < self.player.pos.y {
+                powerup.pos.y += velocity;
 
-        // Draw the invaders
-        for row in &self.invaders.grid {
-            for invader in row.iter().flatten() {
-                blit(screen, &invader.pos, &invader.sprite);
+                // Handle collisions
+                if self.collision.powerup_to_player(powerup, &self.player) {
+                    collected.push(powerup.kind);
+                    destroy.push(i);
+                }
+            } else {
+                destroy.push(i);
             }
         }
 
-        // Draw the shields
-        for shield in &self.shields {
-            blit(screen, &shield.pos, &shield.sprite);
+        // Destroy dead power-ups
+        for &i in destroy.iter().rev() {
+            self.powerups.remove(i);
         }
 
-        // Draw the player
-        blit(screen, &self.player.pos, &self.player.sprite);
-
-        // Draw the bullet
-        if let Some(bullet) = &self.bullet {
-            blit(screen, &bullet.pos, &bullet.sprite);
+        // Apply the effects of collected power-ups
+        for kind in collected {
+            self.collect_powerup(kind);
         }
+    }
+
+    /// Advance the world by an arbitrary amount of time, in fixed time steps.
+    ///
+    /// The time delta is accumulated, and [`World::update`] is called once for each whole
+    /// [`TIME_STEP`]. Leftover time carries over to the next call. To avoid falling ever further
+    /// behind after a long stall (e.g. a breakpoint), at most [`World::set_max_frames_per_update`]
+    /// steps are run, and any excess time is discarded.
+    ///
+    /// Only the events from the last step are available from [`World::events`].
+    ///
+    /// # Panics
+    ///
+    /// Panics in deterministic mode in debug builds, since the number of steps depends on
+    /// wall-clock time. See [`WorldBuilder::deterministic`].
+    ///
+    /// # Arguments
+    ///
+    /// * `dt` - The time elapsed since the last call.
+    /// * `controls` - The player inputs, used for every step.
+    ///
+    /// # Returns
+    ///
+    /// The number of fixed time steps that were run.
+    pub fn advance(&mut self, dt: Duration, controls: &Controls) -> usize {
+        debug_assert!(
+            !self.deterministic,
+            "Wall-clock time cannot be used in deterministic mode, call `World::update` instead"
+        );
 
-        // Draw lasers
-        for laser in self.lasers.iter() {
-            blit(screen, &laser.pos, &laser.sprite);
+        self.accumulator += dt;
+
+        let mut frames = 0;
+        while self.accumulator >= TIME_STEP && frames < self.max_frames_per_update {
+            self.accumulator -= TIME_STEP;
+            self.update(controls);
+            frames += 1;
         }
 
-        // Draw debug information
-        if self.debug {
-            debug::draw_invaders(screen, &self.invaders, &self.collision);
-            debug::draw_bullet(screen, self.bullet.as_ref());
-            debug::draw_lasers(screen, &self.lasers);
-            debug::draw_player(screen, &self.player, &self.collision);
-            debug::draw_shields(screen, &self.shields, &self.collision);
+        // Discard the time that we could not catch up on
+        if self.accumulator >= TIME_STEP {
+            self.accumulator = Duration::default();
         }
+
+        frames
     }
 
-    fn step_invaders(&mut self) {
-        let (_, right, _, left) = self.invaders.get_bounds();
-        let (invader, is_leader) =
-            next_invader(&mut self.invaders.grid, &mut self.invaders.stepper);
+    /// Set the maximum number of fixed time steps run by each call to [`World::advance`].
+    ///
+    /// The default is 60, or a quarter of a second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_frames` is 0.
+    pub fn set_max_frames_per_update(&mut self, max_frames: usize) {
+        assert!(max_frames > 0);
 
-        // The leader controls the fleet
-        if is_leader {
-            // The leader first commands the fleet to stop descending
-            self.invaders.descend = false;
+        self.max_frames_per_update = max_frames;
+    }
 
-            // Then the leader redirects the fleet when they reach the boundaries
-            match self.invaders.direction {
-                Direction::Left => {
-                    if left < 2 {
-                        self.invaders.bounds.pos.x += 2;
-                        self.invaders.bounds.pos.y += 8;
-                        self.invaders.descend = true;
-                        self.invaders.direction = Direction::Right;
-                    } else {
-                        self.invaders.bounds.pos.x -= 2;
-                    }
-                }
-                Direction::Right => {
-                    if right > WIDTH - 2 {
-                        self.invaders.bounds.pos.x -= 2;
-                        self.invaders.bounds.pos.y += 8;
-                        self.invaders.descend = true;
-                        self.invaders.direction = Direction::Left;
-                    } else {
-                        self.invaders.bounds.pos.x += 2;
-                    }
-                }
-                _ => unreachable!(),
+    /// Step the simulation backward, restoring the state from an earlier update.
+    ///
+    /// Up to one second of updates can be rewound. Rewinding further stops at the oldest state
+    /// available. Does nothing unless rewinding is enabled with [`WorldBuilder::rewind`].
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - The number of calls to [`World::update`] to undo.
+    pub fn rewind(&mut self, frames: usize) {
+        let mut snapshot = None;
+        for _ in 0..frames {
+            match self.snapshots.pop_back() {
+                Some(s) => snapshot = Some(s),
+                None => break,
             }
         }
 
-        // Every invader in the fleet moves 2px per frame
-        match self.invaders.direction {
-            Direction::Left => invader.pos.x -= 2,
-            Direction::Right => invader.pos.x += 2,
-            _ => unreachable!(),
+        if let Some(snapshot) = snapshot {
+            snapshot.restore(self);
+            self.events.clear();
         }
+    }
 
-        // And they descend 8px on command
-        if self.invaders.descend {
-            invader.pos.y += 8;
-
-            // One of the end scenarios
-            if invader.pos.y + 8 >= self.player.pos.y {
-                self.gameover = true;
+    /// Repeatedly update the world with the same inputs until a condition is met.
+    ///
+    /// This is mostly useful for writing concise behavior tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `controls` - The player inputs, used for every update.
+    /// * `max_frames` - The maximum number of updates to run.
+    /// * `pred` - The condition to wait for. It is tested before each update.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the condition was met within `max_frames` updates.
+    pub fn simulate_until(
+        &mut self,
+        controls: Controls,
+        max_frames: usize,
+        pred: impl Fn(&World) -> bool,
+    ) -> bool {
+        for _ in 0..max_frames {
+            if pred(self) {
+                return true;
             }
-        }
 
-        // Animate the invader
-        invader.sprite.step_frame(&self.assets);
+            self.update(&controls);
+        }
 
-        // They also shoot lasers at random with a 1:50 chance
-        let r = self.prng.next_u32() as usize;
-        let chance = r % 50;
-        if self.lasers.len() < 3 && chance == 0 {
-            // Pick a random column to begin searching for an invader that can fire a laser
-            let col = r / 50 % COLS;
-            let invader = self.invaders.get_closest_invader(col);
+        pred(self)
+    }
 
-            let laser = Laser {
-                sprite: SpriteRef::new(&self.assets, Frame::Laser1, Duration::from_millis(16)),
-                pos: invader.pos + LASER_OFFSET,
-                dt: Duration::default(),
-            };
-            self.lasers.push(laser);
-        }
+    /// Draw the internal state to the screen.
+    ///
+    /// Calling this method more than once without an `update` call between is a no-op.
+    pub fn draw(&mut self, screen: &mut [u8]) {
+        self.render(screen);
     }
 
-    fn step_player(&mut self, controls: &Controls) {
-        let frames = self.player.update();
-        let width = self.player.sprite.width();
+    /// Draw a frame to a screen-sized pixel buffer, for [`World::draw`] and
+    /// [`World::frame_checksum`].
+    fn render(&self, screen: &mut [u8]) {
+        // Clear the screen
+        clear(screen);
 
-        match controls.direction {
-            Direction::Left => {
-                if self.player.pos.x > width {
-                    self.player.pos.x -= frames;
-                    self.player.sprite.animate(&self.assets);
-                }
-            }
+        // Draw the play field below the HUD strip
+        for command in self.play_field_commands() {
+            command.offset(self.hud_height).draw(screen);
+        }
 
-            Direction::Right => {
-                if self.player.pos.x < WIDTH - width * 2 {
-                    self.player.pos.x += frames;
-                    self.player.sprite.animate(&self.assets);
-                }
-            }
-            _ => (),
+        // Draw debug information
+        if self.debug {
+            let play_area = &mut screen[self.hud_height * WIDTH * 4..];
+            debug::draw_invaders(play_area, &self.invaders, &self.collision);
+            debug::draw_bullets(play_area, &self.bullets);
+            debug::draw_lasers(play_area, &self.lasers);
+            debug::draw_player(play_area, &self.player, &self.collision);
+            debug::draw_shields(play_area, &self.shields, &self.collision);
         }
 
-        if controls.fire && self.bullet.is_none() {
-            self.bullet = Some(Bullet {
-                sprite: SpriteRef::new(&self.assets, Frame::Bullet1, Duration::from_millis(32)),
-                pos: self.player.pos + BULLET_OFFSET,
-                dt: Duration::default(),
-            });
+        // Mirror the play field, but keep the HUD readable
+        if self.mirror {
+            mirror(screen);
+        }
+
+        // Draw the warning, HUD, and countdown over the top
+        for command in self.overlay_commands() {
+            command.draw(screen);
         }
     }
-}
+
+    /// Describe everything that [`World::draw`] would draw, without touching a pixel buffer.
+    ///
+    /// The commands are in drawing order, so they can be composited as-is, or sorted by
+    /// [`DrawCommand::z`] after being mixed with other graphics. Positions include the mirroring
+    /// and HUD offset applied by [`World::draw`]. Debug visualizations are not included.
+    pub fn render_list(&self) -> Vec<DrawCommand> {
+        self.play_field_commands()
+            .into_iter()
+            .map(|command| {
+                let command = if self.mirror {
+                    command.mirrored()
+                } else {
+                    command
+                };
+
+                command.offset(self.hud_height)
+            })
+            .chain(self.overlay_commands())
+            .collect()
+    }
+
+    /// Draw commands for the play field, before it is mirrored or offset below the HUD.
+    fn play_field_commands(&self) -> Vec<DrawCommand> {
+        let mut commands = Vec::new();
+
+        // The invaders
+        for (y, row) in self.invaders.grid.iter().enumerate() {
+            for (col, invader) in row.iter().enumerate() {
+                if let Some(invader) = invader {
+                    let tint = if invader.flash > Duration::default() {
+                        FLASH_COLOR
+                    } else if self.banded {
+                        BAND_COLORS[y]
+                    } else {
+                        self.palette.invaders
+                    };
+                    let pos = self.bob_pos(invader.pos, col);
+                    commands.push(DrawCommand::sprite(
+                        pos,
+                        &invader.sprite,
+                        Layer::Invaders,
+                        Some(tint),
+                    ));
+                }
+            }
+        }
+
+        // The dying invaders
+        for ghost in self.ghosts.iter().filter(|ghost| ghost.visible()) {
+            commands.push(DrawCommand::sprite(
+                ghost.pos,
+                &ghost.sprite,
+                Layer::Invaders,
+                Some(FLASH_COLOR),
+            ));
+        }
+
+        // The shields
+        for shield in &self.shields {
+            commands.push(DrawCommand::sprite(
+                shield.pos,
+                &shield.sprite,
+                Layer::Shields,
+                Some(self.palette.shields),
+            ));
+        }
+
+        // The player
+        commands.push(DrawCommand::sprite(
+            self.player.pos,
+            &self.player.sprite,
+            Layer::Player,
+            Some(self.palette.player),
+        ));
+
+        // The bullets
+        for bullet in &self.bullets {
+            commands.push(DrawCommand::sprite(
+                bullet.pos,
+                &bullet.sprite,
+                Layer::Projectiles,
+                self.bullet_tint(bullet),
+            ));
+        }
+
+        // The lasers
+        for laser in &self.lasers {
+            commands.push(DrawCommand::sprite(
+                laser.pos,
+                &laser.sprite,
+                Layer::Projectiles,
+                self.laser_color,
+            ));
+        }
+
+        // The UFO, facing the direction it travels
+        if let Some(ufo) = &self.ufo {
+            let mut command = DrawCommand::sprite(ufo.pos, &ufo.sprite, Layer::Ufo, None);
+            if let Graphic::Sprite { flipped, .. } = &mut command.graphic {
+                *flipped = ufo.direction == Direction::Left;
+            }
+            commands.push(command);
+        }
+
+        // The power-ups
+        for powerup in &self.powerups {
+            commands.push(DrawCommand::sprite(
+                powerup.pos,
+                &powerup.sprite,
+                Layer::PowerUps,
+                None,
+            ));
+        }
+
+        // The ground
+        if let Some(y) = self.baseline {
+            commands.push(DrawCommand {
+                pos: Point::new(0, y),
+                graphic: Graphic::Line(Point::new(WIDTH - 1, y)),
+                z: Layer::Ground,
+                tint: Some(BASELINE_COLOR),
+            });
+        }
+
+        commands
+    }
+
+    /// Draw commands for everything drawn over the play field, in screen coordinates.
+    fn overlay_commands(&self) -> Vec<DrawCommand> {
+        let mut commands = Vec::new();
+        let hud_height = if self.hud_height > 0 {
+            self.hud_height
+        } else {
+            HUD_HEIGHT
+        };
+
+        // The last life warning
+        if self.warning_visible() {
+            commands.push(DrawCommand {
+                pos: Point::new(0, hud_height),
+                graphic: Graphic::Outline {
+                    width: WIDTH,
+                    height: HEIGHT - hud_height,
+                },
+                z: Layer::Warning,
+                tint: Some(WARNING_COLOR),
+            });
+        }
+
+        // The HUD in its strip, or over the top of the play area
+        for (pos, text) in self.hud_text(WIDTH, hud_height) {
+            commands.push(DrawCommand {
+                pos,
+                graphic: Graphic::Text(text),
+                z: Layer::Hud,
+                tint: Some(HUD_COLOR),
+            });
+        }
+
+        // The wave countdown
+        if let Some(seconds) = self.countdown_seconds() {
+            let text = seconds.to_string();
+            let x = (WIDTH - text_width(&text)) / 2;
+            commands.push(DrawCommand {
+                pos: Point::new(x, COUNTDOWN_Y + self.hud_height),
+                graphic: Graphic::Text(text),
+                z: Layer::Hud,
+                tint: Some(HUD_COLOR),
+            });
+        }
+
+        commands
+    }
+
+    /// A hash of the logical game state, e.g. for verifying that two simulations have not
+    /// diverged.
+    ///
+    /// All randomness in the world comes from the seeded PRNG, so two worlds with the same seed
+    /// and inputs always have the same hash. The hash does not depend on the process or build, so
+    /// it can be compared between runs on the same platform.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+        Snapshot::new(self).hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// A checksum of the frame that [`World::draw`] would draw for the current state.
+    ///
+    /// The frame is drawn to an internal buffer, so this is not free; `draw` never computes it.
+    /// The checksum is stable across platforms and builds, so it can be pinned in golden-value
+    /// tests to catch rendering regressions.
+    pub fn frame_checksum(&self) -> u64 {
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        self.render(&mut screen);
+
+        fnv1a(&screen)
+    }
+
+    /// Draw only the HUD (score, high score, lives, and level) to a separate pixel buffer.
+    ///
+    /// This allows the HUD to be placed anywhere around the play area, e.g. in a strip above or
+    /// below it, instead of overlapping it as [`World::draw`] does.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - An RGBA pixel buffer with the given dimensions.
+    /// * `width` - Width of the pixel buffer.
+    /// * `height` - Height of the pixel buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is smaller than `width * height * 4` bytes.
+    pub fn draw_hud_to(&mut self, frame: &mut [u8], width: usize, height: usize) {
+        assert!(frame.len() >= width * height * 4);
+
+        self.draw_hud(frame, width, height);
+    }
+
+    /// Mirror the play field horizontally.
+    ///
+    /// The frame drawn by [`World::draw`] is flipped (excluding the HUD), and the player's
+    /// left/right controls are swapped to match what is shown on screen.
+    pub fn set_mirror(&mut self, mirror: bool) {
+        self.mirror = mirror;
+    }
+
+    /// Set the speed of the player's bullets, in pixels per second.
+    ///
+    /// See [`WorldBuilder::bullet_speed`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` is 0 or greater than 960.
+    pub fn set_bullet_speed(&mut self, speed: usize) {
+        assert!(speed > 0 && speed <= MAX_PROJECTILE_SPEED);
+
+        self.bullet_speed = speed;
+    }
+
+    /// Set the speed of the invaders' lasers, in pixels per second.
+    ///
+    /// See [`WorldBuilder::laser_speed`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` is 0 or greater than 960.
+    pub fn set_laser_speed(&mut self, speed: usize) {
+        assert!(speed > 0 && speed <= MAX_PROJECTILE_SPEED);
+
+        self.laser_speed = speed;
+    }
+
+    /// Limit the score, like the fixed number of digits on the arcade counter.
+    ///
+    /// # Arguments
+    ///
+    /// * `cap` - The highest possible score. `None` (the default) removes the cap. What happens to
+    ///   points scored beyond it is chosen with [`World::set_score_overflow`].
+    ///
+    /// Each [`ScoreTable::extends`] milestone is still awarded only once, even after the score
+    /// rolls over, and milestones above the cap are never awarded. The current score is not
+    /// changed until more points are scored. [`World::score`] is a `u32`, so it stops at
+    /// `u32::MAX` when the cap is higher (or unset), but all points scored are still counted.
+    pub fn set_score_cap(&mut self, cap: Option<u64>) {
+        self.score_cap = cap;
+    }
+
+    /// Choose what happens to points scored beyond the cap set with [`World::set_score_cap`].
+    ///
+    /// The default is [`ScoreOverflow::Rollover`], like the arcade counter.
+    pub fn set_score_overflow(&mut self, overflow: ScoreOverflow) {
+        self.score_overflow = overflow;
+    }
+
+    /// Draw a ground line across the play area, like the arcade cabinet.
+    ///
+    /// # Arguments
+    ///
+    /// * `baseline` - The row to draw the ground at, in play area coordinates (excluding the
+    ///   [`World::hud_height`] offset). `None` (the default) removes the ground. If the ground is
+    ///   above the player, lasers stop at the ground, and invaders that touch it end the game.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `baseline` is below the bottom of the play area, which is shortened by the HUD
+    /// height.
+    pub fn set_baseline(&mut self, baseline: Option<usize>) {
+        if let Some(y) = baseline {
+            assert!(
+                y < HEIGHT - self.hud_height,
+                "Baseline is below the bottom of the play area"
+            );
+        }
+
+        self.baseline = baseline;
+    }
+
+    /// Enable the knockback gameplay variant.
+    ///
+    /// The first laser hit on each life does not cost a life. Instead, it knocks the player aside
+    /// and briefly stuns them, so movement inputs are ignored. Firing is still allowed.
+    pub fn set_knockback(&mut self, knockback: bool) {
+        self.knockback = knockback;
+    }
+
+    /// Keep destroyed invaders on screen for a moment, blinking in the flash color as they die.
+    ///
+    /// Dying invaders are only drawn; they are removed from the fleet immediately, so they cannot
+    /// be hit again or fire lasers.
+    pub fn set_death_flash(&mut self, death_flash: bool) {
+        self.death_flash = death_flash;
+        if !death_flash {
+            self.ghosts.clear();
+        }
+    }
+
+    /// Tint the player's bullets with an RGBA color, to tell them apart from lasers.
+    ///
+    /// The tint multiplies each color channel of the sprite; the bullet sprites are white, so they
+    /// are drawn in exactly this color. By default, bullets are drawn with their sprite colors.
+    pub fn set_bullet_color(&mut self, color: [u8; 4]) {
+        self.bullet_color = Some(color);
+    }
+
+    /// Tint the bullets fired during a combo with an RGBA color, as feedback for the streak.
+    ///
+    /// Like [`World::set_bullet_color`], the tint multiplies the white bullet sprite. See
+    /// [`World::combo`]. By default, these bullets are drawn like any other bullet.
+    pub fn set_combo_bullet_color(&mut self, color: [u8; 4]) {
+        self.combo_bullet_color = Some(color);
+    }
+
+    /// The number of invaders destroyed in quick succession, or 0 when there is no streak.
+    ///
+    /// Each invader destroyed within a second of the last extends the combo.
+    pub fn combo(&self) -> u32 {
+        self.combo
+    }
+
+    /// Tint the invaders' lasers with an RGBA color, to tell them apart from bullets.
+    ///
+    /// The tint multiplies each color channel of the sprite; the laser sprites are white, so they
+    /// are drawn in exactly this color. By default, lasers are drawn with their sprite colors.
+    pub fn set_laser_color(&mut self, color: [u8; 4]) {
+        self.laser_color = Some(color);
+    }
+
+    /// Tint the invaders in the classic arcade color bands, instead of the level palette.
+    ///
+    /// The top two rows are drawn in one color, and the rest in another.
+    pub fn set_banded(&mut self, banded: bool) {
+        self.banded = banded;
+    }
+
+    /// Make the invaders bob up and down by a pixel, for a livelier look.
+    ///
+    /// This only affects how the invaders are drawn; they collide at their usual positions.
+    pub fn set_bob(&mut self, bob: bool) {
+        self.bob = bob;
+        self.bob_dt = Duration::default();
+    }
+
+    /// Choose how the fire button shoots the cannon.
+    ///
+    /// In [`FireMode::Hold`], the cannon fires when the button is released. Holding it for half a
+    /// second first charges a piercing shot, which passes through every invader in its path.
+    pub fn set_fire_mode(&mut self, fire_mode: FireMode) {
+        self.fire_mode = fire_mode;
+        self.charge_timer = Duration::default();
+    }
+
+    /// Set the length of the countdown shown before each wave begins.
+    ///
+    /// While counting down, the world is frozen: the invaders do not march, and the player cannot
+    /// move or fire. A zero duration (the default) disables the countdown. The countdown also
+    /// begins immediately for the current wave.
+    pub fn set_wave_countdown(&mut self, countdown: Duration) {
+        self.wave_countdown = countdown;
+        self.countdown = countdown;
+    }
+
+    /// The whole number of seconds remaining in the wave countdown, rounded up.
+    ///
+    /// Returns `None` when no countdown is in progress.
+    pub fn countdown_seconds(&self) -> Option<u64> {
+        if self.countdown > Duration::default() {
+            let seconds = self.countdown.as_secs();
+            let partial = self.countdown.subsec_nanos() > 0;
+            Some(seconds + partial as u64)
+        } else {
+            None
+        }
+    }
+
+    /// Immediately spawn the UFO at its entry edge, unless it is already on screen.
+    ///
+    /// The UFO alternates between entering from the left and right edges of the screen, and
+    /// travels toward the opposite edge.
+    ///
+    /// The UFO normally appears at random. This is mostly useful for testing and debugging.
+    pub fn spawn_ufo(&mut self) {
+        if self.ufo.is_none() {
+            let mut ufo = Ufo::new(Point::new(0, UFO_Y), Direction::Right);
+            if !self.ufo_from_left {
+                ufo.pos.x = WIDTH - ufo.sprite.width();
+                ufo.direction = Direction::Left;
+            }
+
+            self.ufo = Some(ufo);
+            self.ufo_from_left = !self.ufo_from_left;
+            self.events.push(Event::UfoSpawned);
+        }
+    }
+
+    /// Test whether the UFO is currently on screen.
+    pub fn ufo_active(&self) -> bool {
+        self.ufo.is_some()
+    }
+
+    /// The events that occurred during the last call to [`World::update`], in resolution order.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// The height of the HUD strip above the play area.
+    ///
+    /// When this is 0, the HUD is drawn over the top of the play area. Otherwise, the play area is
+    /// drawn below the HUD, offset by this many pixels. See [`WorldBuilder::hud_height`].
+    pub fn hud_height(&self) -> usize {
+        self.hud_height
+    }
+
+    /// The collision rectangles for every active entity, tagged by collision layer.
+    ///
+    /// This is the geometry used by the broad phase of collision detection, which is useful for
+    /// external debug renderers. Rectangles are in screen coordinates, including the
+    /// [`World::hud_height`] offset.
+    pub fn collision_rects(&self) -> Vec<(CollisionLayer, Rect)> {
+        let invaders = self
+            .invaders
+            .grid
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|invader| {
+                let rect = Rect::from_drawable(&invader.pos, &invader.sprite);
+                (CollisionLayer::Invader, rect)
+            });
+        let shields = self.shields.iter().map(|shield| {
+            let rect = Rect::from_drawable(&shield.pos, &shield.sprite);
+            (CollisionLayer::Shield, rect)
+        });
+        let player = {
+            let rect = Rect::from_drawable(&self.player.pos, &self.player.sprite);
+            (CollisionLayer::Player, rect)
+        };
+        let bullets = self.bullets.iter().map(|bullet| {
+            let rect = Rect::from_drawable(&bullet.pos, &bullet.sprite);
+            (CollisionLayer::Bullet, rect)
+        });
+        let lasers = self.lasers.iter().map(|laser| {
+            let rect = Rect::from_drawable(&laser.pos, &laser.sprite);
+            (CollisionLayer::Laser, rect)
+        });
+        let powerups = self.powerups.iter().map(|powerup| {
+            let rect = Rect::from_drawable(&powerup.pos, &powerup.sprite);
+            (CollisionLayer::PowerUp, rect)
+        });
+        let ufo = self.ufo.iter().map(|ufo| {
+            let rect = Rect::from_drawable(&ufo.pos, &ufo.sprite);
+            (CollisionLayer::Ufo, rect)
+        });
+
+        invaders
+            .chain(shields)
+            .chain(std::iter::once(player))
+            .chain(bullets)
+            .chain(lasers)
+            .chain(powerups)
+            .chain(ufo)
+            .map(|(layer, rect)| {
+                let offset = Point::new(0, self.hud_height);
+                (layer, Rect::new(&(rect.p1 + offset), &(rect.p2 + offset)))
+            })
+            .collect()
+    }
+
+    /// The shields, in order from left to right, as `(x, y, intact)` tuples.
+    ///
+    /// Positions are the top-left corner in screen coordinates, including the
+    /// [`World::hud_height`] offset. `intact` is the fraction of the shield that has not been shot
+    /// away, from 0.0 to 1.0.
+    pub fn shields(&self) -> impl Iterator<Item = (usize, usize, f32)> + '_ {
+        self.shields.iter().map(move |shield| {
+            let y = shield.pos.y + self.hud_height;
+            (shield.pos.x, y, shield.intact())
+        })
+    }
+
+    /// The estimated number of holes in each shield, in order from left to right.
+    pub fn shield_holes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.shields.iter().map(Shield::holes)
+    }
+
+    /// The power-ups currently falling, as `(kind, x, y)` tuples.
+    ///
+    /// Positions are the top-left corner in screen coordinates, including the
+    /// [`World::hud_height`] offset.
+    pub fn powerups(&self) -> impl Iterator<Item = (PowerUpKind, usize, usize)> + '_ {
+        self.powerups
+            .iter()
+            .map(move |powerup| (powerup.kind, powerup.pos.x, powerup.pos.y + self.hud_height))
+    }
+
+    /// Preview the formation of the next wave, e.g. to show it between waves.
+    ///
+    /// # Returns
+    ///
+    /// The position and kind of each invader, as `(x, y, kind)` tuples. Positions are the top-left
+    /// corner in screen coordinates, including the [`World::hud_height`] offset.
+    pub fn next_formation_preview(&self) -> Vec<(usize, usize, InvaderKind)> {
+        let invaders = Invaders::new(&self.assets, &self.score_table, &self.invader_hp);
+
+        invaders
+            .grid
+            .iter()
+            .flatten()
+            .flatten()
+            .filter_map(|invader| {
+                let kind = invader.sprite.frame().invader_kind()?;
+                Some((invader.pos.x, invader.pos.y + self.hud_height, kind))
+            })
+            .collect()
+    }
+
+    /// The number of invaders still alive in the current wave.
+    pub fn invaders_remaining(&self) -> usize {
+        self.invaders.grid.iter().flatten().flatten().count()
+    }
+
+    /// The time between steps for each invader in the formation.
+    ///
+    /// Invaders step one at a time, so the formation marches faster as invaders are destroyed.
+    /// This is useful for syncing the tempo of the marching sound.
+    pub fn invader_step_interval(&self) -> Duration {
+        self.invader_frame * self.invaders_remaining() as u32
+    }
+
+    /// The total number of points still available on screen.
+    ///
+    /// This is the sum of the scores for every live invader, plus the largest possible bonus for
+    /// the UFO while it is on screen.
+    pub fn points_remaining(&self) -> u32 {
+        let invaders = self
+            .invaders
+            .grid
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|invader| invader.score)
+            .sum::<u32>();
+        let ufo = if self.ufo.is_some() {
+            self.score_table
+                .ufo_bonus
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        invaders + ufo
+    }
+
+    /// The animation phase of the invader formation.
+    ///
+    /// Invaders are animated one at a time as they march, and the phase flips each time a new sweep
+    /// through the formation begins. This is useful for syncing the marching sound to the visuals.
+    pub fn formation_phase(&self) -> bool {
+        self.invaders.phase
+    }
+
+    /// Test whether the game is paused.
+    ///
+    /// Pressing [`Controls::pause`] toggles the pause. The world does not update while paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// How long it has been since the player last moved or fired.
+    ///
+    /// This is useful for switching to an attract mode when nobody is playing.
+    pub fn idle_time(&self) -> Duration {
+        self.idle_time
+    }
+
+    /// The overall state of the game.
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// How the game ended, or `None` while it is still in progress.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// A snapshot of commonly needed read-only values, e.g. for front-ends and logging.
+    pub fn stats(&self) -> GameStats {
+        GameStats {
+            score: self.score,
+            high_score: self.high_score,
+            lives: self.lives,
+            level: self.level,
+            invaders_remaining: self.invaders_remaining(),
+            state: self.state,
+        }
+    }
+
+    /// The palette used to tint the current level.
+    pub fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    /// The player's current score.
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// The highest score reached in this `World`.
+    pub fn high_score(&self) -> u32 {
+        self.high_score
+    }
+
+    /// The number of lives the player has remaining.
+    pub fn lives(&self) -> u8 {
+        self.lives
+    }
+
+    /// The current level, starting at 1.
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    fn draw_hud(&self, screen: &mut [u8], width: usize, height: usize) {
+        for (pos, text) in self.hud_text(width, height) {
+            draw_text(screen, width, height, &pos, &text, HUD_COLOR);
+        }
+    }
+
+    /// Lay out the HUD text in a strip with the given dimensions.
+    fn hud_text(&self, width: usize, height: usize) -> [(Point, String); 3] {
+        let y = height.saturating_sub(GLYPH_HEIGHT) / 2;
+
+        // Score on the left
+        let score = format!("SCORE {:05}", self.score);
+        let score_pos = Point::new(HUD_MARGIN, y);
+
+        // High score in the center
+        let high_score = format!("HI {:05}", self.high_score);
+        let x = width.saturating_sub(text_width(&high_score)) / 2;
+        let high_score_pos = Point::new(x, y);
+
+        // Lives and level on the right
+        let status = format!("LIVES {} LV {}", self.lives, self.level);
+        let x = width.saturating_sub(text_width(&status) + HUD_MARGIN);
+        let status_pos = Point::new(x, y);
+
+        [
+            (score_pos, score),
+            (high_score_pos, high_score),
+            (status_pos, status),
+        ]
+    }
+
+    /// Advance to the next level with a fresh formation, or win the game after the final level.
+    ///
+    /// A perfect wave (no lives lost and no shield damage from lasers) awards a bonus.
+    fn next_level(&mut self) {
+        self.events.push(Event::LevelCleared);
+        self.add_score(self.score_table.wave_bonus);
+
+        if !self.damage_taken_this_wave {
+            self.add_score(self.score_table.perfect_bonus);
+            self.events.push(Event::PerfectWave);
+        }
+        self.damage_taken_this_wave = false;
+
+        // Clearing the final wave wins the game
+        if Some(self.level) == self.final_level {
+            self.state = GameState::GameOver;
+            self.outcome = Some(Outcome::Won);
+            return;
+        }
+
+        self.level += 1;
+        self.palette = level_palette(self.level);
+        self.reset_formation();
+    }
+
+    /// Replace the invader fleet with a new formation, and remove any lasers in flight and dying
+    /// invaders.
+    ///
+    /// The wave countdown is restarted, if enabled, followed by the grace period before the
+    /// invaders open fire.
+    fn reset_formation(&mut self) {
+        self.invaders = Invaders::new(&self.assets, &self.score_table, &self.invader_hp);
+        self.lasers.clear();
+        self.ghosts.clear();
+        self.countdown = self.wave_countdown;
+        self.grace = GRACE_PERIOD;
+    }
+
+    /// The position to draw an invader at, including the bob animation offset.
+    fn bob_pos(&self, pos: Point, col: usize) -> Point {
+        if !self.bob {
+            return pos;
+        }
+
+        let step = (self.bob_dt.as_nanos() / BOB_STEP.as_nanos()) as usize;
+        let offset = BOB_OFFSETS[(step + col) % BOB_OFFSETS.len()];
+        let y = if offset < 0 {
+            pos.y.saturating_sub(offset.unsigned_abs())
+        } else {
+            pos.y + offset as usize
+        };
+
+        Point::new(pos.x, y)
+    }
+
+    /// Draw a random number for the invaders. All of their randomness must come from here.
+    fn random(&mut self) -> u32 {
+        self.check_seeded();
+        self.prng.next_u32()
+    }
+
+    /// Draw a random number for UFOs and power-ups. All of their randomness must come from here.
+    fn bonus_random(&mut self) -> u32 {
+        self.check_seeded();
+        self.bonus_prng.next_u32()
+    }
+
+    /// Catch nondeterminism in deterministic mode: randomness drawn outside of a fixed time step
+    /// depends on when the caller happens to call in, which a replay cannot reproduce.
+    fn check_seeded(&self) {
+        debug_assert!(
+            !self.deterministic || self.stepping,
+            "Randomness outside of `World::update` cannot be used in deterministic mode"
+        );
+    }
+
+    /// The lowest point that lasers and invaders can reach: the player's row, or the ground if it
+    /// is higher.
+    fn floor(&self) -> usize {
+        match self.baseline {
+            Some(y) => y.min(self.player.pos.y),
+            None => self.player.pos.y,
+        }
+    }
+
+    /// Handle the actions that only respond to a fresh press, and remember the controls for the
+    /// next frame.
+    fn apply_controls_edge(&mut self, controls: &Controls) {
+        if controls.pressed(&self.prev_controls, Action::Pause) {
+            self.paused = !self.paused;
+        }
+
+        self.prev_controls = *controls;
+    }
+
+    /// The tint for a bullet, if any.
+    fn bullet_tint(&self, bullet: &Bullet) -> Option<[u8; 4]> {
+        if bullet.combo {
+            self.combo_bullet_color.or(self.bullet_color)
+        } else {
+            self.bullet_color
+        }
+    }
+
+    /// Test whether the pulsing last life warning is currently visible.
+    fn warning_visible(&self) -> bool {
+        let pulses = self.warning_dt.as_nanos() / WARNING_PULSE.as_nanos();
+
+        self.lives == 1 && pulses & 1 == 0
+    }
+
+    fn add_score(&mut self, score: u32) {
+        let previous = self.points;
+        self.points = previous.saturating_add(u64::from(score));
+        let shown = match (self.score_cap, self.score_overflow) {
+            (Some(cap), ScoreOverflow::Rollover) if self.points > cap => self.points % (cap + 1),
+            (Some(cap), ScoreOverflow::Clamp) if self.points > cap => cap,
+            _ => self.points,
+        };
+        self.score = shown.min(u64::from(u32::MAX)) as u32;
+        self.high_score = self.high_score.max(self.score);
+
+        // Award an extra life for each milestone crossed, in order. Milestones that the score
+        // cannot show are never reached.
+        let cap = self.score_cap.unwrap_or(u64::MAX);
+        let mut extends = self
+            .score_table
+            .extends
+            .iter()
+            .copied()
+            .filter(|&milestone| {
+                let milestone = u64::from(milestone);
+                previous < milestone && milestone <= self.points.min(cap)
+            })
+            .collect::<Vec<_>>();
+        extends.sort_unstable();
+        extends.dedup();
+        for milestone in extends {
+            self.lives = self.lives.saturating_add(1);
+            self.events.push(Event::ExtraLife(milestone));
+        }
+    }
+
+    fn step_bullet(&mut self, bullet: &mut Option<Bullet>) {
+        let b = bullet.as_mut().unwrap();
+        let velocity = projectile_step(&mut b.travel, self.bullet_speed);
+
+        if b.pos.y > velocity {
+            b.pos.y -= velocity;
+            b.sprite.animate(&self.assets);
+            let pos = b.pos;
+
+            // Handle collisions
+            let hit = self.collision.bullet_to_invader(bullet, &mut self.invaders);
+            if let Some(InvaderHit::Damaged) = hit {
+                self.events.push(Event::InvaderDamaged);
+            } else if let Some(InvaderHit::Destroyed(invader)) = hit {
+                self.add_score(invader.score);
+                self.events.push(Event::InvaderKilled);
+                self.combo += 1;
+                self.combo_timer = COMBO_WINDOW;
+
+                // The dying invader lingers for a moment, but no longer collides or fires
+                if self.death_flash {
+                    self.ghosts.push(Ghost {
+                        sprite: invader.sprite,
+                        pos: invader.pos,
+                        ttl: GHOST_DURATION,
+                    });
+                }
+
+                // Destroyed invaders occasionally drop a power-up
+                let chance = self.bonus_random() % POWERUP_CHANCE;
+                if chance == 0 {
+                    self.spawn_powerup(PowerUpKind::Burst, pos);
+                }
+
+                // Destroying the entire fleet advances to the next level
+                if self.invaders.shrink_bounds() {
+                    self.next_level();
+                }
+            } else if self.collision.bullet_to_ufo(bullet, &mut self.ufo) {
+                let r = self.bonus_random() as usize;
+                let bonus = &self.score_table.ufo_bonus;
+                let bonus = bonus[r % bonus.len()];
+                self.add_score(bonus);
+                self.events.push(Event::UfoDestroyed);
+            } else {
+                self.collision.bullet_to_shield(bullet, &mut self.shields);
+            }
+        } else {
+            *bullet = None;
+        }
+    }
+
+    fn spawn_powerup(&mut self, kind: PowerUpKind, pos: Point) {
+        self.powerups.push(PowerUp::new(kind, pos));
+    }
+
+    fn collect_powerup(&mut self, kind: PowerUpKind) {
+        self.events.push(Event::PowerUpCollected(kind));
+
+        match kind {
+            PowerUpKind::Burst => {
+                // Fire a volley of bullets, regardless of any bullets already in flight
+                for i in 0..BURST_BULLETS {
+                    let offset = BULLET_OFFSET.y + i * BURST_SPACING;
+                    let mut pos = self.player.pos + Point::new(BULLET_OFFSET.x, 0);
+                    pos.y = pos.y.saturating_sub(offset);
+
+                    self.fire_bullet(pos, false);
+                }
+            }
+        }
+    }
+
+    fn fire_bullet(&mut self, pos: Point, piercing: bool) {
+        self.bullets.push(Bullet {
+            sprite: SpriteRef::new(&self.assets, Frame::Bullet1, Duration::from_millis(32)),
+            pos,
+            travel: 0,
+            piercing,
+            pierced: Vec::new(),
+            combo: self.combo > 1,
+        });
+        self.events.push(Event::BulletFired);
+    }
+
+    fn step_invaders(&mut self) {
+        // The last invader makes a frantic dash, moving erratically
+        let step = if self.invaders_remaining() == 1 {
+            let r = self.random() as usize;
+            FINALE_STEPS[r % FINALE_STEPS.len()]
+        } else {
+            2
+        };
+
+        let (_, right, _, left) = self.invaders.get_bounds();
+        let floor = self.floor();
+        let (invader, is_leader) =
+            next_invader(&mut self.invaders.grid, &mut self.invaders.stepper);
+
+        // The leader controls the fleet
+        if is_leader {
+            // The leader first commands the fleet to stop descending
+            self.invaders.descend = false;
+
+            // And begins a new sweep, which toggles the animation frame of the fleet
+            self.invaders.phase = !self.invaders.phase;
+
+            // Then the leader redirects the fleet when they reach the boundaries
+            match self.invaders.direction {
+                Direction::Left => {
+                    if left < step {
+                        self.invaders.bounds.pos.x += step;
+                        self.invaders.bounds.pos.y += 8;
+                        self.invaders.descend = true;
+                        self.invaders.direction = Direction::Right;
+                    } else {
+                        self.invaders.bounds.pos.x -= step;
+                    }
+                }
+                Direction::Right => {
+                    if right > WIDTH - step {
+                        self.invaders.bounds.pos.x -= step;
+                        self.invaders.bounds.pos.y += 8;
+                        self.invaders.descend = true;
+                        self.invaders.direction = Direction::Left;
+                    } else {
+                        self.invaders.bounds.pos.x += step;
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        // Every invader in the fleet moves 2px per frame, except during the finale
+        match self.invaders.direction {
+            Direction::Left => invader.pos.x -= step,
+            Direction::Right => invader.pos.x += step,
+            _ => unreachable!(),
+        }
+
+        // And they descend 8px on command
+        if self.invaders.descend {
+            invader.pos.y += 8;
+
+            // One of the end scenarios
+            if invader.pos.y + 8 >= floor {
+                self.state = GameState::GameOver;
+                self.outcome = Some(Outcome::Invaded);
+            }
+        }
+
+        // Animate the invader
+        invader.sprite.step_frame(&self.assets);
+
+        // They also shoot lasers at random, once the grace period is over. The formation reloads
+        // faster as its tempo increases.
+        let r = self.random() as usize;
+        let full_interval = self.invader_frame * (ROWS * COLS) as u32;
+        let tempo = self.invader_step_interval().as_secs_f64() / full_interval.as_secs_f64();
+        let laser_chance = (f64::from(self.laser_chance) * tempo) as usize;
+        let laser_chance = laser_chance.max(MIN_LASER_CHANCE.min(self.laser_chance) as usize);
+        let chance = r % laser_chance;
+        let grace = self.grace > Duration::default();
+        if self.lasers.len() < 3 && chance == 0 && !grace {
+            // Pick a random column to begin searching for an invader that can fire a laser
+            let col = r / laser_chance % COLS;
+            let invader = self.invaders.get_closest_invader(col);
+
+            let laser = Laser {
+                sprite: SpriteRef::new(&self.assets, Frame::Laser1, Duration::from_millis(16)),
+                pos: invader.pos + LASER_OFFSET,
+                travel: 0,
+            };
+            self.lasers.push(laser);
+            self.events.push(Event::LaserFired);
+        }
+    }
+
+    fn step_ufo(&mut self) {
+        match &mut self.ufo {
+            Some(ufo) => {
+                let velocity = ufo.update();
+                let width = ufo.sprite.width();
+
+                // The UFO flies across the screen until it leaves the opposite edge
+                match ufo.direction {
+                    Direction::Left if ufo.pos.x >= velocity => ufo.pos.x -= velocity,
+                    Direction::Right if ufo.pos.x + width + velocity <= WIDTH => {
+                        ufo.pos.x += velocity
+                    }
+                    _ => self.ufo = None,
+                }
+            }
+            None => {
+                let chance = self.bonus_random() % UFO_CHANCE;
+                if chance == 0 {
+                    self.spawn_ufo();
+                }
+            }
+        }
+    }
+
+    fn step_player(&mut self, controls: &Controls) {
+        let frames = self.player.update();
+        let width = self.player.sprite.width();
+
+        // Movement is ignored while stunned
+        let direction = if self.stun_timer > Duration::default() {
+            self.stun_timer = self.stun_timer.saturating_sub(TIME_STEP);
+            Direction::Still
+        } else if self.mirror {
+            controls.direction.mirrored()
+        } else {
+            controls.direction
+        };
+
+        match direction {
+            Direction::Left if self.player.pos.x > width => {
+                self.player.pos.x -= frames;
+                self.player.sprite.animate(&self.assets);
+            }
+
+            Direction::Right if self.player.pos.x < WIDTH - width * 2 => {
+                self.player.pos.x += frames;
+                self.player.sprite.animate(&self.assets);
+            }
+            _ => (),
+        }
+
+        match self.fire_mode {
+            FireMode::Tap => {
+                if controls.held(Action::Fire) && self.bullets.is_empty() {
+                    self.fire_bullet(self.player.pos + BULLET_OFFSET, false);
+                }
+            }
+            FireMode::Hold => {
+                if controls.held(Action::Fire) {
+                    self.charge_timer += TIME_STEP;
+                } else if self.charge_timer > Duration::default() {
+                    // Releasing fire shoots, and a fully charged shot pierces the fleet
+                    let piercing = self.charge_timer >= CHARGE_THRESHOLD;
+                    self.charge_timer = Duration::default();
+
+                    if self.bullets.is_empty() {
+                        self.fire_bullet(self.player.pos + BULLET_OFFSET, piercing);
+                    }
+                }
+            }
+        }
+    }
+}
 
 /// Create a default `World` with a static PRNG seed.
 impl Default for World {
     fn default() -> Self {
-        let seed = (6_364_136_223_846_793_005, 1);
+        World::new(DEFAULT_SEED, false)
+    }
+}
+
+impl Invaders {
+    fn new(assets: &Assets, score_table: &ScoreTable, hp: &[u8; ROWS]) -> Invaders {
+        // TODO: Create invaders one-at-a-time
+        Invaders {
+            grid: make_invader_grid(assets, score_table, hp),
+            stepper: Point::new(COLS - 1, 0),
+            direction: Direction::Right,
+            descend: false,
+            phase: false,
+            bounds: Bounds::default(),
+        }
+    }
+
+    /// Compute the bounding box for the Invader fleet.
+    ///
+    /// # Returns
+    ///
+    /// Tuple of `(top, right, bottom, left)`, e.g. in CSS clockwise order.
+    fn get_bounds(&self) -> (usize, usize, usize, usize) {
+        let width = (self.bounds.right_col - self.bounds.left_col + 1) * GRID.x;
+        let height = (self.bounds.bottom_row - self.bounds.top_row + 1) * GRID.y;
+
+        let top = self.bounds.pos.y;
+        let bottom = top + height;
+        let left = self.bounds.pos.x;
+        let right = left + width;
+
+        (top, right, bottom, left)
+    }
+
+    /// Resize the bounds to fit the live invaders.
+    ///
+    /// # Returns
+    ///
+    /// `true` when all invaders have been destroyed.
+    fn shrink_bounds(&mut self) -> bool {
+        let mut top = ROWS;
+        let mut right = 0;
+        let mut bottom = 0;
+        let mut left = COLS;
+
+        // Scan through the entire grid
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, col) in row.iter().enumerate() {
+                if col.is_some() {
+                    // Build a boundary box of invaders in the grid
+                    if top > y {
+                        top = y;
+                    }
+                    if bottom < y {
+                        bottom = y;
+                    }
+                    if left > x {
+                        left = x;
+                    }
+                    if right < x {
+                        right = x;
+                    }
+                }
+            }
+        }
+
+        if top > bottom || left > right {
+            // No more invaders left alive
+            return true;
+        }
+
+        // Adjust the bounding box position
+        self.bounds.pos.x += (left - self.bounds.left_col) * GRID.x;
+        self.bounds.pos.y += (top - self.bounds.top_row) * GRID.y;
+
+        // Adjust the bounding box columns and rows
+        self.bounds.left_col = left;
+        self.bounds.right_col = right;
+        self.bounds.top_row = top;
+        self.bounds.bottom_row = bottom;
+
+        // No more changes
+        false
+    }
+
+    fn get_closest_invader(&self, mut col: usize) -> &Invader {
+        let mut row = ROWS - 1;
+        loop {
+            if self.grid[row][col].is_some() {
+                return self.grid[row][col].as_ref().unwrap();
+            }
+
+            if row == 0 {
+                row = ROWS - 1;
+                col += 1;
+                if col == COLS {
+                    col = 0;
+                }
+            } else {
+                row -= 1;
+            }
+        }
+    }
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self {
+            pos: START,
+            left_col: 0,
+            right_col: COLS - 1,
+            top_row: 0,
+            bottom_row: ROWS - 1,
+        }
+    }
+}
+
+impl Shield {
+    fn new(assets: &Assets, pos: Point) -> Shield {
+        let sprite = Sprite::new(assets, Frame::Shield1);
+        let pristine = Rc::clone(&assets.sprites().get(&Frame::Shield1).unwrap().2);
+
+        Shield {
+            sprite,
+            pristine,
+            pos,
+        }
+    }
+
+    /// Test whether the pixel at the given shield-local coordinates has been shot away.
+    fn is_cleared(&self, x: usize, y: usize) -> bool {
+        let i = (x + y * self.sprite.width()) * 4;
+        let was_lit = self.pristine[i..i + 3].iter().any(|&c| c > 0);
+
+        was_lit && !self.sprite.is_lit(x, y)
+    }
+
+    /// Deform the shield by clearing a cluster of pixels around the given shield-local point.
+    ///
+    /// The cluster is shaped by the projectile's heading, eroding deeper in the direction of
+    /// travel than behind the impact.
+    fn damage(&mut self, pos: &Point, heading: Heading) {
+        const DEPTH: isize = 3;
+
+        let width = self.sprite.width() as isize;
+        let height = self.sprite.height() as isize;
+        let (x, y) = (pos.x as isize, pos.y as isize);
+
+        for along in -1..=DEPTH {
+            // Wide at the impact, then narrowing toward the tip
+            let spread = match along {
+                -1 => 0,
+                0 | 1 => 2,
+                _ => 1,
+            };
+            let y = match heading {
+                Heading::Up => y - along,
+                Heading::Down => y + along,
+            };
+            if y < 0 || y >= height {
+                continue;
+            }
+
+            for x in (x - spread)..=(x + spread) {
+                if x >= 0 && x < width {
+                    self.sprite.clear(x as usize, y as usize);
+                }
+            }
+        }
+    }
+
+    /// The fraction of the undamaged shield that remains, from 0.0 to 1.0.
+    fn intact(&self) -> f32 {
+        let width = self.sprite.width();
+        let (mut total, mut cleared) = (0, 0);
+        for y in 0..self.sprite.height() {
+            for x in 0..width {
+                if self.sprite.is_lit(x, y) || self.is_cleared(x, y) {
+                    total += 1;
+                }
+                if self.is_cleared(x, y) {
+                    cleared += 1;
+                }
+            }
+        }
+
+        (total - cleared) as f32 / total as f32
+    }
+
+    /// Pack the shield deformation into a compact mask, with one bit per pixel.
+    ///
+    /// Bits are set for pixels that have been shot away, in row-major order with the most
+    /// significant bit first.
+    fn damage_mask(&self) -> Vec<u8> {
+        let width = self.sprite.width();
+        let len = width * self.sprite.height();
+        let cleared = (0..len)
+            .map(|i| self.is_cleared(i % width, i / width))
+            .collect::<Vec<_>>();
+
+        cleared
+            .chunks(8)
+            .map(|bits| {
+                bits.iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, &bit)| byte | (bit as u8) << (7 - i))
+            })
+            .collect()
+    }
+
+    /// Deform the shield with a mask created by [`Shield::damage_mask`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mask is not the expected size.
+    fn apply_damage_mask(&mut self, mask: &[u8]) {
+        let width = self.sprite.width();
+        let len = width * self.sprite.height();
+        assert!(
+            mask.len() * 8 >= len && mask.len() * 8 < len + 8,
+            "Wrong mask size"
+        );
+
+        for i in 0..len {
+            if mask[i / 8] & (0x80 >> (i % 8)) != 0 {
+                self.sprite.clear(i % width, i / width);
+            }
+        }
+    }
+
+    /// Estimate the number of holes in the shield.
+    ///
+    /// A hole is a connected region of pixels that have been shot away. Pixels that are
+    /// transparent in the undamaged sprite are not counted.
+    fn holes(&self) -> usize {
+        let width = self.sprite.width();
+        let height = self.sprite.height();
+        let mut visited = vec![false; width * height];
+        let mut queue = VecDeque::new();
+        let mut holes = 0;
+
+        for y in 0..height {
+            for x in 0..width {
+                if visited[x + y * width] || !self.is_cleared(x, y) {
+                    continue;
+                }
+
+                // Flood fill the connected region
+                holes += 1;
+                visited[x + y * width] = true;
+                queue.push_back((x, y));
+
+                while let Some((x, y)) = queue.pop_front() {
+                    let neighbors = [
+                        (x.wrapping_sub(1), y),
+                        (x + 1, y),
+                        (x, y.wrapping_sub(1)),
+                        (x, y + 1),
+                    ];
+
+                    for (x, y) in neighbors {
+                        if x < width
+                            && y < height
+                            && !visited[x + y * width]
+                            && self.is_cleared(x, y)
+                        {
+                            visited[x + y * width] = true;
+                            queue.push_back((x, y));
+                        }
+                    }
+                }
+            }
+        }
+
+        holes
+    }
+}
+
+/// Clear the screen
+fn clear(screen: &mut [u8]) {
+    for (i, byte) in screen.iter_mut().enumerate() {
+        *byte = if i % 4 == 3 { 255 } else { 0 };
+    }
+}
+
+/// Create a grid of invaders, with hit points for each row from top to bottom.
+fn make_invader_grid(
+    assets: &Assets,
+    score_table: &ScoreTable,
+    hp: &[u8; ROWS],
+) -> Vec<Vec<Option<Invader>>> {
+    use Frame::*;
+
+    const BLIPJOY_OFFSET: Point = Point::new(3, 4);
+    const FERRIS_OFFSET: Point = Point::new(2, 5);
+    const CTHULHU_OFFSET: Point = Point::new(1, 3);
+
+    (0..1)
+        .map(|y| {
+            (0..COLS)
+                .map(|x| {
+                    Some(Invader {
+                        sprite: SpriteRef::new(assets, Blipjoy1, Duration::default()),
+                        pos: START + BLIPJOY_OFFSET + Point::new(x, y) * GRID,
+                        score: score_table.rows[y],
+                        hp: hp[y],
+                        flash: Duration::default(),
+                    })
+                })
+                .collect()
+        })
+        .chain((1..3).map(|y| {
+            (0..COLS)
+                .map(|x| {
+                    Some(Invader {
+                        sprite: SpriteRef::new(assets, Ferris1, Duration::default()),
+                        pos: START + FERRIS_OFFSET + Point::new(x, y) * GRID,
+                        score: score_table.rows[y],
+                        hp: hp[y],
+                        flash: Duration::default(),
+                    })
+                })
+                .collect()
+        }))
+        .chain((3..5).map(|y| {
+            (0..COLS)
+                .map(|x| {
+                    Some(Invader {
+                        sprite: SpriteRef::new(assets, Cthulhu1, Duration::default()),
+                        pos: START + CTHULHU_OFFSET + Point::new(x, y) * GRID,
+                        score: score_table.rows[y],
+                        hp: hp[y],
+                        flash: Duration::default(),
+                    })
+                })
+                .collect()
+        }))
+        .collect()
+}
+
+fn next_invader<'a>(
+    invaders: &'a mut [Vec<Option<Invader>>],
+    stepper: &mut Point,
+) -> (&'a mut Invader, bool) {
+    let mut is_leader = false;
+
+    loop {
+        // Iterate through the entire grid
+        stepper.x += 1;
+        if stepper.x >= COLS {
+            stepper.x = 0;
+            if stepper.y == 0 {
+                stepper.y = ROWS - 1;
+
+                // After a full cycle, the next invader will be the leader
+                is_leader = true;
+            } else {
+                stepper.y -= 1;
+            }
+        }
+
+        if invaders[stepper.y][stepper.x].is_some() {
+            return (invaders[stepper.y][stepper.x].as_mut().unwrap(), is_leader);
+        }
+    }
+}
+
+/// Scramble the bits of a value, for deriving well-distributed PRNG seeds from small inputs.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Hash a byte slice with the 64-bit FNV-1a algorithm.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hasher.write(bytes);
+
+    hasher.finish()
+}
+
+/// A `Hasher` for the 64-bit FNV-1a algorithm.
+///
+/// Unlike the standard library's default hasher, the output is guaranteed to be stable.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Destroy an invader by firing a bullet directly beneath it.
+    fn shoot_invader(world: &mut World, col: usize, row: usize) {
+        let pos = {
+            let invader = world.invaders.grid[row][col].as_ref().unwrap();
+            let center = invader.sprite.width() / 2;
+            invader.pos + Point::new(center, invader.sprite.height() + 1)
+        };
+        world.fire_bullet(pos, false);
+
+        for _ in 0..16 {
+            world.update(&Controls::default());
+            if world.events().contains(&Event::InvaderKilled) {
+                return;
+            }
+
+            // Keep the bullet under the invader, in case it moved
+            if let (Some(bullet), Some(invader)) = (
+                world.bullets.last_mut(),
+                world.invaders.grid[row][col].as_ref(),
+            ) {
+                bullet.pos.x = invader.pos.x + invader.sprite.width() / 2;
+            }
+        }
+
+        panic!("Invader at ({}, {}) was not destroyed", col, row);
+    }
+
+    /// Create a builder with the same seed as `World::default`.
+    fn test_builder() -> WorldBuilder {
+        WorldBuilder::new(DEFAULT_SEED)
+    }
+
+    /// Destroy the entire fleet, advancing to the next level.
+    fn clear_invaders(world: &mut World) {
+        clear_invaders_except(world, (COLS, ROWS));
+    }
+
+    /// Destroy every invader except the one at `(col, row)`.
+    fn clear_invaders_except(world: &mut World, keep: (usize, usize)) {
+        let cells = (0..ROWS)
+            .flat_map(|row| (0..COLS).map(move |col| (col, row)))
+            .filter(|&cell| cell != keep)
+            .collect::<Vec<_>>();
+        world.with_cleared_cells(&cells);
+    }
+
+    /// Move the whole fleet to row `y`, and step it down one row.
+    fn descend_invaders_to(world: &mut World, y: usize) {
+        // The leader steps first, then commands the fleet to descend
+        world.step_invaders();
+        world.invaders.descend = true;
+        for invader in world.invaders.grid.iter_mut().flatten().flatten() {
+            invader.pos.y = y;
+        }
+        world.step_invaders();
+    }
+
+    /// Add a laser at `pos`.
+    fn spawn_laser(world: &mut World, pos: Point) {
+        world.lasers.push(Laser {
+            sprite: SpriteRef::new(&world.assets, Frame::Laser1, Duration::default()),
+            pos,
+            travel: 0,
+        });
+    }
+
+    /// Add a laser just above the player, so it hits on the next update.
+    fn drop_laser_on_player(world: &mut World) {
+        let pos = world.player.pos + Point::new(4, 0);
+        spawn_laser(world, Point::new(pos.x, pos.y - 4));
+    }
+
+    /// Test if any pixel in the rectangle is lit.
+    fn is_lit(screen: &[u8], width: usize, p1: Point, p2: Point) -> bool {
+        (p1.y..p2.y).any(|y| {
+            (p1.x..p2.x).any(|x| {
+                let i = (x + y * width) * 4;
+                screen[i..i + 3].iter().any(|&c| c > 0)
+            })
+        })
+    }
+
+    #[test]
+    fn test_draw_hud_to() {
+        let mut world = World::default();
+        world.add_score(120);
+
+        // A full-size buffer proves that nothing but the HUD is drawn
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw_hud_to(&mut screen, WIDTH, HUD_HEIGHT);
+
+        let score_width = text_width("SCORE 00120");
+        assert!(is_lit(
+            &screen,
+            WIDTH,
+            Point::new(HUD_MARGIN, 0),
+            Point::new(HUD_MARGIN + score_width, HUD_HEIGHT),
+        ));
+
+        // The invaders are drawn by `draw`, but not by `draw_hud_to`
+        let (top, right, bottom, left) = world.invaders.get_bounds();
+        let (p1, p2) = (Point::new(left, top), Point::new(right, bottom));
+        assert!(!is_lit(&screen, WIDTH, p1, p2));
+        let mut full = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut full);
+        assert!(is_lit(&full, WIDTH, p1, p2));
+
+        // The score glyphs match a directly rendered string
+        let mut expected = vec![0; WIDTH * HUD_HEIGHT * 4];
+        let y = (HUD_HEIGHT - GLYPH_HEIGHT) / 2;
+        draw_text(
+            &mut expected,
+            WIDTH,
+            HUD_HEIGHT,
+            &Point::new(HUD_MARGIN, y),
+            "SCORE 00120",
+            HUD_COLOR,
+        );
+        for row in y..y + GLYPH_HEIGHT {
+            let a = (HUD_MARGIN + row * WIDTH) * 4;
+            let b = a + score_width * 4;
+            assert_eq!(screen[a..b], expected[a..b], "Score glyphs differ");
+        }
+    }
+
+    #[test]
+    fn test_simultaneous_fire_order() {
+        let controls = Controls {
+            direction: Direction::Still,
+            fire: true,
+            pause: false,
+        };
+
+        let run = || {
+            let mut world = World::new((0x1234_5678, 0x9abc_def0), false);
+            let mut log = Vec::new();
+
+            for frame in 0..20_000 {
+                world.update(&controls);
+
+                let events = world.events();
+                let bullet = events.iter().position(|&e| e == Event::BulletFired);
+                let laser = events.iter().position(|&e| e == Event::LaserFired);
+                if let (Some(bullet), Some(laser)) = (bullet, laser) {
+                    // Player fire is always resolved before invader fire
+                    assert!(bullet < laser, "Bullet must be resolved first");
+                    log.push((frame, events.to_vec()));
+                }
+            }
+
+            log
+        };
+
+        let first = run();
+        assert!(
+            !first.is_empty(),
+            "Expected a frame with simultaneous spawns"
+        );
+        assert_eq!(first, run(), "Event order differs between runs");
+    }
+
+    #[test]
+    fn test_shield_holes() {
+        let mut world = World::default();
+        let shield = &mut world.shields[0];
+        assert_eq!(shield.holes(), 0, "Undamaged shield has no holes");
+
+        // Punch two holes far enough apart that they cannot touch
+        shield.damage(&Point::new(5, 4), Heading::Down);
+        shield.damage(&Point::new(16, 9), Heading::Down);
+        assert_eq!(shield.holes(), 2);
+
+        // Overlapping damage does not create a new hole
+        shield.damage(&Point::new(6, 5), Heading::Down);
+        assert_eq!(shield.holes(), 2);
+
+        assert_eq!(world.shield_holes().collect::<Vec<_>>(), [2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_with_cleared_cells() {
+        let mut world = World::default();
+
+        // Clear the entire left column and top row
+        let cells = (0..ROWS)
+            .map(|row| (0, row))
+            .chain((1..COLS).map(|col| (col, 0)))
+            .collect::<Vec<_>>();
+        world.with_cleared_cells(&cells);
+
+        for &(col, row) in &cells {
+            assert!(world.invaders.grid[row][col].is_none());
+        }
+        assert!(world.invaders.grid[1][1].is_some());
+
+        let bounds = &world.invaders.bounds;
+        assert_eq!(bounds.left_col, 1);
+        assert_eq!(bounds.right_col, COLS - 1);
+        assert_eq!(bounds.top_row, 1);
+        assert_eq!(bounds.bottom_row, ROWS - 1);
+        assert_eq!(bounds.pos.x, START.x + GRID.x);
+        assert_eq!(bounds.pos.y, START.y + GRID.y);
+        assert_eq!(world.state(), GameState::Playing);
+    }
+
+    #[test]
+    fn test_burst_powerup() {
+        let mut world = World::default();
+        let pos = Point::new(world.player.pos.x, world.player.pos.y - 4);
+        world.spawn_powerup(PowerUpKind::Burst, pos);
+
+        world.update(&Controls::default());
+
+        let fired = world
+            .events()
+            .iter()
+            .filter(|&&e| e == Event::BulletFired)
+            .count();
+        assert!(world
+            .events()
+            .contains(&Event::PowerUpCollected(PowerUpKind::Burst)));
+        assert_eq!(fired, BURST_BULLETS);
+        assert_eq!(world.bullets.len(), BURST_BULLETS);
+
+        // Every bullet in the volley is at a different height
+        let mut heights = world.bullets.iter().map(|b| b.pos.y).collect::<Vec<_>>();
+        heights.sort_unstable();
+        heights.dedup();
+        assert_eq!(heights.len(), BURST_BULLETS);
+        assert!(world.powerups.is_empty());
+    }
+
+    #[test]
+    fn test_collision_rects() {
+        let mut world = World::default();
+        world.with_cleared_cells(&[(0, 0), (5, 2)]);
+
+        let rects = world.collision_rects();
+
+        let player = Rect::from_drawable(&world.player.pos, &world.player.sprite);
+        assert!(rects.contains(&(CollisionLayer::Player, player)));
+
+        let mut invaders = 0;
+        for invader in world.invaders.grid.iter().flatten().flatten() {
+            let rect = Rect::from_drawable(&invader.pos, &invader.sprite);
+            assert!(rects.contains(&(CollisionLayer::Invader, rect)));
+            invaders += 1;
+        }
+
+        let count = |layer| rects.iter().filter(|(l, _)| *l == layer).count();
+        assert_eq!(invaders, COLS * ROWS - 2);
+        assert_eq!(count(CollisionLayer::Invader), invaders);
+        assert_eq!(count(CollisionLayer::Player), 1);
+        assert_eq!(count(CollisionLayer::Shield), 4);
+        assert_eq!(count(CollisionLayer::Bullet), 0);
+    }
+
+    #[test]
+    fn test_level_palette() {
+        let mut world = World::default();
+
+        // Advance two levels by destroying the entire fleet twice
+        clear_invaders(&mut world);
+        assert_eq!(world.level(), 2);
+        assert!(world.events().contains(&Event::LevelCleared));
+        let palette = world.palette();
+        assert_eq!(palette, level_palette(2));
+
+        clear_invaders(&mut world);
+        assert_eq!(world.level(), 3);
+        assert_eq!(world.palette(), level_palette(3));
+        assert_ne!(palette, world.palette());
+
+        // The palette is applied when drawing
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut screen);
+        let invader = world.invaders.grid[0][0].as_ref().unwrap();
+        let found = (0..invader.sprite.height()).any(|y| {
+            (0..invader.sprite.width()).any(|x| {
+                let i = (invader.pos.x + x + (invader.pos.y + y) * WIDTH) * 4;
+                screen[i..i + 4] == world.palette().invaders
+            })
+        });
+        assert!(found, "Invader is not tinted");
+    }
+
+    #[test]
+    fn test_mirror() {
+        let mut world = World::default();
+        world.player.pos.x = 16;
+
+        let mut normal = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut normal);
+
+        world.set_mirror(true);
+        let mut mirrored = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut mirrored);
+
+        // The player drawn on the left appears on the right
+        let (p1, p2) = {
+            let rect = Rect::from_drawable(&world.player.pos, &world.player.sprite);
+            (rect.p1, rect.p2)
+        };
+        assert!(is_lit(&normal, WIDTH, p1, p2));
+        assert!(!is_lit(&mirrored, WIDTH, p1, p2));
+        let p1 = Point::new(WIDTH - p2.x, p1.y);
+        let p2 = Point::new(WIDTH - 16, p2.y);
+        assert!(is_lit(&mirrored, WIDTH, p1, p2));
+
+        // The entire play field is flipped
+        for y in HUD_HEIGHT..HEIGHT {
+            for x in 0..WIDTH {
+                let i = (x + y * WIDTH) * 4;
+                let j = (WIDTH - 1 - x + y * WIDTH) * 4;
+                assert_eq!(normal[i..i + 4], mirrored[j..j + 4]);
+            }
+        }
+
+        // Controls are inverted
+        let controls = Controls {
+            direction: Direction::Left,
+            fire: false,
+            pause: false,
+        };
+        for _ in 0..8 {
+            world.update(&controls);
+        }
+        assert!(world.player.pos.x > 16, "Player should move right");
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut world = World::default();
+        world.with_cleared_cells(&[(0, 0), (1, 0), (2, 0)]);
+        world.add_score(30);
+        for _ in 0..100 {
+            world.update(&Controls::default());
+        }
+
+        let stats = world.stats();
+        assert_eq!(stats.score, world.score());
+        assert_eq!(stats.high_score, world.high_score());
+        assert_eq!(stats.lives, world.lives());
+        assert_eq!(stats.level, world.level());
+        assert_eq!(stats.invaders_remaining, world.invaders_remaining());
+        assert_eq!(stats.state, world.state());
+
+        assert_eq!(stats.score, 30);
+        assert_eq!(stats.invaders_remaining, COLS * ROWS - 3);
+        assert_eq!(stats.state, GameState::Playing);
+    }
+
+    #[test]
+    fn test_perfect_wave() {
+        let mut world = World::default();
+        clear_invaders_except(&mut world, (0, ROWS - 1));
+        world.lasers.clear();
+
+        shoot_invader(&mut world, 0, ROWS - 1);
+        assert!(world.events().contains(&Event::LevelCleared));
+        assert!(world.events().contains(&Event::PerfectWave));
+        assert_eq!(world.score(), 10 + ScoreTable::default().perfect_bonus);
+    }
+
+    #[test]
+    fn test_imperfect_wave() {
+        let mut world = World::default();
+        clear_invaders_except(&mut world, (0, ROWS - 1));
+        world.lasers.clear();
+
+        // Drop a laser on the player
+        drop_laser_on_player(&mut world);
+        world.update(&Controls::default());
+        assert!(world.events().contains(&Event::PlayerHit));
+        assert_eq!(world.lives(), LIVES - 1);
+
+        shoot_invader(&mut world, 0, ROWS - 1);
+        assert!(world.events().contains(&Event::LevelCleared));
+        assert!(!world.events().contains(&Event::PerfectWave));
+        assert_eq!(world.score(), 10);
+    }
+
+    #[test]
+    fn test_wave_countdown() {
+        let mut world = World::default();
+        world.set_wave_countdown(Duration::from_secs(1));
+        assert_eq!(world.countdown_seconds(), Some(1));
+
+        let controls = Controls {
+            direction: Direction::Left,
+            fire: true,
+            pause: false,
+        };
+        let stepper = world.invaders.stepper;
+        let player = world.player.pos;
+
+        // Nothing happens for the entire second
+        let mut frames = 0;
+        while world.countdown_seconds().is_some() {
+            world.update(&controls);
+            assert_eq!(world.invaders.stepper, stepper);
+            assert_eq!(world.player.pos, player);
+            assert!(world.bullets.is_empty(), "Firing should be blocked");
+            assert!(world.events().is_empty());
+            frames += 1;
+        }
+        assert!((FPS..=FPS + 1).contains(&frames));
+
+        // Then the wave begins
+        for _ in 0..8 {
+            world.update(&controls);
+        }
+        assert_ne!(world.invaders.stepper, stepper);
+        assert!(!world.bullets.is_empty());
+
+        // The countdown restarts with the next wave
+        clear_invaders(&mut world);
+        assert_eq!(world.countdown_seconds(), Some(1));
+    }
+
+    #[test]
+    fn test_spawn_ufo() {
+        let mut world = World::default();
+        assert!(!world.ufo_active());
+
+        world.spawn_ufo();
+        assert!(world.ufo_active());
+        assert!(world.events().contains(&Event::UfoSpawned));
+        let start = world.ufo.as_ref().unwrap().pos;
+        assert_eq!(start.x, 0);
+
+        for _ in 0..40 {
+            world.update(&Controls::default());
+        }
+        let pos = world.ufo.as_ref().unwrap().pos;
+        assert!(pos.x > start.x, "UFO should move across the screen");
+
+        // Spawning again while the UFO is active is a no-op
+        world.spawn_ufo();
+        assert_eq!(world.ufo.as_ref().unwrap().pos, pos);
+
+        // Eventually the UFO leaves the screen
+        let mut frames = 0;
+        while world.ufo_active() {
+            world.update(&Controls::default());
+            frames += 1;
+            assert!(frames < WIDTH * 8, "UFO should leave the screen");
+        }
+    }
+
+    #[test]
+    fn test_ufo_entry_sides() {
+        let mut world = World::default();
+
+        // The first UFO enters from the left and travels right
+        world.spawn_ufo();
+        let ufo = world.ufo.as_ref().unwrap();
+        assert_eq!(ufo.pos.x, 0);
+        assert_eq!(ufo.direction, Direction::Right);
+        world.ufo = None;
+
+        // The second UFO enters from the right and travels left
+        world.spawn_ufo();
+        let ufo = world.ufo.as_ref().unwrap();
+        let width = ufo.sprite.width();
+        let height = ufo.sprite.height();
+        let start = ufo.pos;
+        assert_eq!(start.x + width, WIDTH);
+        assert_eq!(ufo.direction, Direction::Left);
+
+        for _ in 0..40 {
+            world.update(&Controls::default());
+        }
+        let pos = world.ufo.as_ref().unwrap().pos;
+        assert!(pos.x < start.x, "UFO should travel left");
+
+        // The sprite is drawn flipped to face the direction of travel
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut screen);
+        let ufo = world.ufo.as_ref().unwrap();
+        for y in 0..height {
+            for x in 0..width {
+                let lit = is_lit(
+                    &screen,
+                    WIDTH,
+                    Point::new(pos.x + x, pos.y + y),
+                    Point::new(pos.x + x + 1, pos.y + y + 1),
+                );
+                assert_eq!(lit, ufo.sprite.is_lit(width - 1 - x, y));
+            }
+        }
+
+        // And the third is back on the left
+        world.ufo = None;
+        world.spawn_ufo();
+        assert_eq!(world.ufo.as_ref().unwrap().direction, Direction::Right);
+    }
+
+    #[test]
+    fn test_custom_score_table() {
+        let score_table = ScoreTable {
+            rows: [5, 4, 3, 2, 1],
+            ufo_bonus: vec![7],
+            wave_bonus: 11,
+            perfect_bonus: 13,
+            extends: Vec::new(),
+        };
+        let mut world = WorldBuilder::new((1, 2)).score_table(score_table).build();
+        world.lasers.clear();
+
+        shoot_invader(&mut world, 3, 0);
+        assert_eq!(world.score(), 5);
+        shoot_invader(&mut world, 3, 2);
+        assert_eq!(world.score(), 5 + 3);
+
+        // Clearing the rest of the wave awards both bonuses
+        clear_invaders_except(&mut world, (0, ROWS - 1));
+        world.lasers.clear();
+        shoot_invader(&mut world, 0, ROWS - 1);
+        assert_eq!(world.score(), 5 + 3 + 1 + 11 + 13);
+    }
+
+    #[test]
+    fn test_simulate_until() {
+        let mut world = World::default();
+        let controls = Controls {
+            direction: Direction::Still,
+            fire: true,
+            pause: false,
+        };
+
+        let success = world.simulate_until(controls, FPS * 10, |world| {
+            world.invaders_remaining() < COLS * ROWS
+        });
+        assert!(success, "An invader should be destroyed within 10 seconds");
+        assert!(world.invaders_remaining() < COLS * ROWS);
+
+        // Impossible conditions give up after the maximum number of frames
+        assert!(!world.simulate_until(Controls::default(), 10, |_| false));
+    }
+
+    #[test]
+    fn test_invader_hit_flash() {
+        let mut world = test_builder().invader_hp([1, 1, 1, 1, 2]).build();
+        world.lasers.clear();
+
+        // Use a palette that is distinct from the flash color
+        world.palette = level_palette(2);
+
+        let col = 4;
+        let row = ROWS - 1;
+        let pos = {
+            let invader = world.invaders.grid[row][col].as_ref().unwrap();
+            invader.pos + Point::new(invader.sprite.width() / 2, invader.sprite.height() + 1)
+        };
+        world.fire_bullet(pos, false);
+        let hit = world.simulate_until(Controls::default(), 16, |world| {
+            world.events().contains(&Event::InvaderDamaged)
+        });
+        assert!(hit, "Invader should be damaged");
+
+        let invader_color = |world: &mut World| {
+            let mut screen = vec![0; WIDTH * HEIGHT * 4];
+            world.draw(&mut screen);
+
+            let invader = world.invaders.grid[row][col].as_ref().unwrap();
+            let (x, y) = (0..invader.sprite.height())
+                .flat_map(|y| (0..invader.sprite.width()).map(move |x| (x, y)))
+                .find(|&(x, y)| {
+                    let i = (x + y * invader.sprite.width()) * 4;
+                    invader.sprite.pixels()[i..i + 3].iter().any(|&c| c > 0)
+                })
+                .unwrap();
+            let i = (invader.pos.x + x + (invader.pos.y + y) * WIDTH) * 4;
+            [screen[i], screen[i + 1], screen[i + 2], screen[i + 3]]
+        };
+
+        // The invader survives and flashes
+        let invader = world.invaders.grid[row][col].as_ref().unwrap();
+        assert_eq!(invader.hp, 1);
+        assert!(invader.flash > Duration::default());
+        assert_eq!(invader_color(&mut world), FLASH_COLOR);
+
+        // The flash lasts for the flash duration
+        let frames = (FLASH_DURATION.as_nanos() / TIME_STEP.as_nanos()) as usize;
+        for _ in 0..frames - 1 {
+            world.update(&Controls::default());
+            assert_eq!(invader_color(&mut world), FLASH_COLOR);
+        }
+        for _ in 0..2 {
+            world.update(&Controls::default());
+        }
+        assert_eq!(invader_color(&mut world), world.palette().invaders);
+    }
+
+    #[test]
+    fn test_last_life_warning() {
+        let mut world = World::default();
+        world.lasers.clear();
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        let corner = HUD_HEIGHT * WIDTH * 4;
+
+        // No warning with lives to spare
+        world.draw(&mut screen);
+        assert_ne!(screen[corner..corner + 4], WARNING_COLOR);
+
+        world.lives = 1;
+        let mut visible = Vec::new();
+        for _ in 0..FPS {
+            world.update(&Controls::default());
+            world.draw(&mut screen);
+            visible.push(screen[corner..corner + 4] == WARNING_COLOR);
+        }
+
+        // The border appears immediately, then pulses on and off
+        assert!(visible[0]);
+        let toggles = visible.windows(2).filter(|w| w[0] != w[1]).count();
+        assert_eq!(toggles, 3, "Warning should pulse twice per second");
+    }
+
+    #[test]
+    fn test_hud_height() {
+        let mut world = World::default();
+        let mut offset_world = test_builder().hud_height(HUD_HEIGHT).build();
+        assert_eq!(world.hud_height(), 0);
+        assert_eq!(offset_world.hud_height(), HUD_HEIGHT);
+
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        let mut offset_screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut screen);
+        offset_world.draw(&mut offset_screen);
+
+        // Invaders and the player are shifted down by the HUD height
+        let rects = world.collision_rects();
+        let offset_rects = offset_world.collision_rects();
+        let layers = [CollisionLayer::Invader, CollisionLayer::Player];
+        for ((layer, rect), (_, offset_rect)) in rects.iter().zip(&offset_rects) {
+            if !layers.contains(layer) {
+                continue;
+            }
+            assert_eq!(offset_rect.p1.y, rect.p1.y + HUD_HEIGHT);
+
+            for y in rect.p1.y..rect.p2.y {
+                let i = (rect.p1.x + y * WIDTH) * 4;
+                let j = (rect.p1.x + (y + HUD_HEIGHT) * WIDTH) * 4;
+                let width = (rect.p2.x - rect.p1.x) * 4;
+                assert_eq!(screen[i..i + width], offset_screen[j..j + width]);
+            }
+        }
+
+        // The HUD strip contains only the HUD
+        let mut hud = vec![0; WIDTH * HEIGHT * 4];
+        clear(&mut hud);
+        offset_world.draw_hud_to(&mut hud, WIDTH, HUD_HEIGHT);
+        let strip = HUD_HEIGHT * WIDTH * 4;
+        assert_eq!(offset_screen[..strip], hud[..strip]);
+    }
+
+    #[test]
+    fn test_points_remaining() {
+        let mut world = World::default();
+        world.lasers.clear();
+        let score_table = ScoreTable::default();
+        let total = score_table.rows.iter().sum::<u32>() * COLS as u32;
+        assert_eq!(world.points_remaining(), total);
+
+        shoot_invader(&mut world, 2, 0);
+        assert_eq!(world.points_remaining(), total - score_table.rows[0]);
+
+        world.spawn_ufo();
+        assert_eq!(
+            world.points_remaining(),
+            total - score_table.rows[0] + 300,
+            "The maximum UFO bonus is included"
+        );
+    }
+
+    #[test]
+    fn test_charge_shot() {
+        let mut world = World::default();
+        world.set_fire_mode(FireMode::Hold);
+
+        // Charge past the threshold
+        let hold = Controls {
+            fire: true,
+            ..Controls::default()
+        };
+        while world.charge_timer <= CHARGE_THRESHOLD {
+            world.lasers.clear();
+            world.update(&hold);
+        }
+        assert!(world.bullets.is_empty(), "Holding fire does not shoot");
+
+        // Line up beneath a column between two shields, and release
+        let center = {
+            let invader = world.invaders.grid[ROWS - 1][2].as_ref().unwrap();
+            invader.pos.x + invader.sprite.width() / 2
+        };
+        world.player.pos.x = center - BULLET_OFFSET.x;
+        world.update(&Controls::default());
+        assert!(world.events().contains(&Event::BulletFired));
+        assert!(world.bullets[0].piercing);
+
+        let mut killed = 0;
+        while !world.bullets.is_empty() {
+            world.lasers.clear();
+            world.update(&Controls::default());
+            killed += world
+                .events()
+                .iter()
+                .filter(|&&event| event == Event::InvaderKilled)
+                .count();
+        }
+        assert!(killed > 1, "Only {} invaders were destroyed", killed);
+    }
+
+    #[test]
+    fn test_daily() {
+        let mut world = World::daily((2024, 6, 1));
+        let mut same_day = World::daily((2024, 6, 1));
+        let mut next_day = World::daily((2024, 6, 2));
+
+        let sequence = (0..16).map(|_| world.prng.next_u32()).collect::<Vec<_>>();
+        let same_sequence = (0..16)
+            .map(|_| same_day.prng.next_u32())
+            .collect::<Vec<_>>();
+        let next_sequence = (0..16)
+            .map(|_| next_day.prng.next_u32())
+            .collect::<Vec<_>>();
+        assert_eq!(sequence, same_sequence);
+        assert_ne!(sequence, next_sequence);
+    }
+
+    #[test]
+    fn test_bob() {
+        /// Find the topmost lit row in the columns of an invader, below the HUD.
+        fn top_row(screen: &[u8], rect: &Rect) -> usize {
+            (HUD_HEIGHT..HEIGHT)
+                .find(|&y| {
+                    let p1 = Point::new(rect.p1.x, y);
+                    let p2 = Point::new(rect.p2.x, y + 1);
+                    is_lit(screen, WIDTH, p1, p2)
+                })
+                .unwrap()
+        }
+
+        let mut world = World::default();
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut screen);
+        let rect = world.collision_rects()[0].1;
+        let logical = top_row(&screen, &rect);
+
+        // The first column is one pixel lower during the second step of the bob animation
+        world.set_bob(true);
+        world.bob_dt = BOB_STEP;
+        world.draw(&mut screen);
+        assert_eq!(top_row(&screen, &rect), logical + 1);
+
+        // Collisions still use the logical position
+        assert_eq!(world.collision_rects()[0].1, rect);
+    }
+
+    #[test]
+    fn test_grace_period() {
+        let mut world = test_builder().laser_chance(1).build();
+
+        // Clearing every invader starts the next wave
+        clear_invaders(&mut world);
+        assert_eq!(world.level(), 2);
+
+        // Invaders hold their fire during the grace period
+        let frames = GRACE_PERIOD.as_nanos() / TIME_STEP.as_nanos();
+        for _ in 0..frames - 1 {
+            world.update(&Controls::default());
+            assert!(!world.events().contains(&Event::LaserFired));
+        }
+        assert!(world.lasers.is_empty());
+
+        // And open fire immediately after
+        let fired = world.simulate_until(Controls::default(), 8, |world| !world.lasers.is_empty());
+        assert!(fired);
+    }
+
+    #[test]
+    fn test_formation_phase() {
+        let mut world = World::default();
+        assert!(!world.formation_phase());
+
+        // The leader begins the first sweep
+        world.step_invaders();
+        let phase = world.formation_phase();
+        assert!(phase);
+
+        // The phase holds for the rest of the sweep
+        for _ in 1..world.invaders_remaining() {
+            world.step_invaders();
+            assert_eq!(world.formation_phase(), phase);
+        }
+
+        // And flips when the next sweep begins
+        world.step_invaders();
+        assert_ne!(world.formation_phase(), phase);
+    }
+
+    #[test]
+    fn test_laser_speed() {
+        let mut world = test_builder().laser_speed(MAX_PROJECTILE_SPEED).build();
+
+        // Fire a laser from the top of the screen, clear of the shields and the player
+        spawn_laser(&mut world, Point::new(8, 0));
+
+        // The laser moves 4px per time step, until it reaches the bottom of the play area
+        let frames = world.player.pos.y / 4;
+        for _ in 0..frames {
+            world.update(&Controls::default());
+        }
+        assert_eq!(world.lasers.len(), 1);
+        assert_eq!(world.lasers[0].pos.y, world.player.pos.y);
+
+        world.update(&Controls::default());
+        assert!(world.lasers.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_laser_speed_tunneling() {
+        World::default().set_laser_speed(MAX_PROJECTILE_SPEED + 1);
+    }
+
+    #[test]
+    fn test_outcome_won() {
+        let mut world = test_builder().final_level(1).build();
+        assert_eq!(world.outcome(), None);
+
+        clear_invaders(&mut world);
+        assert_eq!(world.state(), GameState::GameOver);
+        assert_eq!(world.outcome(), Some(Outcome::Won));
+        assert_eq!(world.level(), 1);
+    }
+
+    #[test]
+    fn test_outcome_lost_all_lives() {
+        let mut world = World {
+            lives: 1,
+            ..World::default()
+        };
+
+        drop_laser_on_player(&mut world);
+        world.update(&Controls::default());
+        assert_eq!(world.state(), GameState::GameOver);
+        assert_eq!(world.outcome(), Some(Outcome::LostAllLives));
+    }
+
+    #[test]
+    fn test_outcome_invaded() {
+        let mut world = World::default();
+
+        // The fleet descends onto the player
+        let y = world.player.pos.y - 8;
+        descend_invaders_to(&mut world, y);
+        assert_eq!(world.state(), GameState::GameOver);
+        assert_eq!(world.outcome(), Some(Outcome::Invaded));
+    }
+
+    #[test]
+    fn test_death_flash() {
+        let mut world = World::default();
+        world.set_death_flash(true);
+        world.lasers.clear();
+
+        // Use a palette that is distinct from the flash color
+        world.palette = level_palette(2);
+        let rect = {
+            let invader = world.invaders.grid[ROWS - 1][4].as_ref().unwrap();
+            Rect::from_drawable(&invader.pos, &invader.sprite)
+        };
+        let flashing = |world: &mut World| {
+            let mut screen = vec![0; WIDTH * HEIGHT * 4];
+            world.draw(&mut screen);
+
+            (rect.p1.y..rect.p2.y).any(|y| {
+                (rect.p1.x..rect.p2.x).any(|x| {
+                    let i = (x + y * WIDTH) * 4;
+                    screen[i..i + 4] == FLASH_COLOR
+                })
+            })
+        };
+        assert!(!flashing(&mut world));
+
+        shoot_invader(&mut world, 4, ROWS - 1);
+        assert_eq!(world.ghosts.len(), 1);
+        assert!(flashing(&mut world));
+
+        // The dying invader no longer collides, and cannot fire
+        let invaders = world
+            .collision_rects()
+            .iter()
+            .filter(|(layer, _)| *layer == CollisionLayer::Invader)
+            .count();
+        assert_eq!(invaders, COLS * ROWS - 1);
+        assert!(world.invaders.grid[ROWS - 1][4].is_none());
+
+        // It blinks while it dies
+        let blink = GHOST_BLINK.as_nanos() / TIME_STEP.as_nanos() + 1;
+        for _ in 0..blink {
+            world.lasers.clear();
+            world.update(&Controls::default());
+        }
+        assert_eq!(world.ghosts.len(), 1);
+        assert!(!flashing(&mut world));
+        for _ in 0..blink {
+            world.lasers.clear();
+            world.update(&Controls::default());
+        }
+        assert!(flashing(&mut world));
+
+        // It vanishes after a few frames
+        let frames = GHOST_DURATION.as_nanos() / TIME_STEP.as_nanos() + 1;
+        for _ in 0..frames {
+            world.lasers.clear();
+            world.update(&Controls::default());
+        }
+        assert!(world.ghosts.is_empty());
+        assert!(!flashing(&mut world));
+
+        // Dying invaders are removed along with the formation
+        clear_invaders_except(&mut world, (0, ROWS - 1));
+        world.lasers.clear();
+        shoot_invader(&mut world, 0, ROWS - 1);
+        assert_eq!(world.level(), 2);
+        assert!(world.ghosts.is_empty());
+    }
+
+    #[test]
+    fn test_controls_from_axes() {
+        for &x_axis in &[0.0, 0.1, -0.1, AXIS_DEADZONE / 2.0] {
+            let controls = World::controls_from_axes(x_axis, false);
+            assert_eq!(controls.direction, Direction::Still, "x_axis = {}", x_axis);
+        }
+        for &x_axis in &[-1.0, -0.5, -AXIS_DEADZONE] {
+            let controls = World::controls_from_axes(x_axis, false);
+            assert_eq!(controls.direction, Direction::Left, "x_axis = {}", x_axis);
+        }
+        for &x_axis in &[1.0, 0.5, AXIS_DEADZONE] {
+            let controls = World::controls_from_axes(x_axis, false);
+            assert_eq!(controls.direction, Direction::Right, "x_axis = {}", x_axis);
+        }
+
+        assert!(World::controls_from_axes(0.0, true).fire);
+    }
+
+    #[test]
+    fn test_frame_checksum() {
+        let mut world = World::default();
+        let checksum = world.frame_checksum();
+        assert_eq!(checksum, 0x643e_acd1_d835_70c5, "Rendering has changed");
+
+        // The checksum covers exactly what is drawn
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut screen);
+        assert_eq!(fnv1a(&screen), checksum);
+
+        world.with_cleared_cells(&[(0, 0)]);
+        assert_ne!(world.frame_checksum(), checksum);
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut world = test_builder().rewind(true).build();
+        let controls = Controls {
+            direction: Direction::Right,
+            fire: true,
+            pause: false,
+        };
+
+        let mut checksums = Vec::new();
+        let mut stats = Vec::new();
+        let mut positions = Vec::new();
+        for _ in 0..30 {
+            checksums.push(world.frame_checksum());
+            stats.push(world.stats());
+            positions.push(world.player.pos);
+            world.update(&controls);
+        }
+
+        // Rewinding restores the state from frame 20
+        world.rewind(10);
+        assert_eq!(world.frame_checksum(), checksums[20]);
+        assert_eq!(world.stats(), stats[20]);
+        assert_eq!(world.player.pos, positions[20]);
+
+        // And replaying from there is deterministic
+        for (i, &checksum) in checksums.iter().enumerate().skip(20) {
+            assert_eq!(world.frame_checksum(), checksum, "Frame {}", i);
+            world.update(&controls);
+        }
+
+        // Rewinding too far stops at the oldest state
+        world.rewind(REWIND_FRAMES * 2);
+        assert_eq!(world.player.pos, PLAYER_START);
+    }
+
+    #[test]
+    fn test_banded() {
+        /// Find the color of the first lit pixel of an invader.
+        fn invader_color(world: &World, screen: &[u8], col: usize, row: usize) -> [u8; 4] {
+            let invader = world.invaders.grid[row][col].as_ref().unwrap();
+            let rect = Rect::from_drawable(&invader.pos, &invader.sprite);
+            (rect.p1.y..rect.p2.y)
+                .flat_map(|y| (rect.p1.x..rect.p2.x).map(move |x| (x + y * WIDTH) * 4))
+                .map(|i| [screen[i], screen[i + 1], screen[i + 2], screen[i + 3]])
+                .find(|color| color[..3].iter().any(|&c| c > 0))
+                .unwrap()
+        }
+
+        let mut world = World::default();
+        world.set_banded(true);
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut screen);
+
+        let top = invader_color(&world, &screen, 0, 0);
+        let bottom = invader_color(&world, &screen, 0, ROWS - 1);
+        assert_eq!(top, BAND_COLORS[0]);
+        assert_eq!(bottom, BAND_COLORS[ROWS - 1]);
+        assert_ne!(top, bottom);
+    }
+
+    #[test]
+    fn test_idle_time() {
+        let mut world = World::default();
+        assert_eq!(world.idle_time(), Duration::default());
+
+        for _ in 0..10 {
+            world.update(&Controls::default());
+        }
+        assert_eq!(world.idle_time(), TIME_STEP * 10);
+
+        world.update(&Controls {
+            fire: true,
+            ..Controls::default()
+        });
+        assert_eq!(world.idle_time(), Duration::default());
+
+        world.update(&Controls::default());
+        assert_eq!(world.idle_time(), TIME_STEP);
+    }
+
+    #[test]
+    fn test_directional_shield_damage() {
+        /// Count the cleared pixels in the top and bottom halves of a shield.
+        fn cleared(shield: &Shield) -> (usize, usize) {
+            let (width, height) = (shield.sprite.width(), shield.sprite.height());
+            let count = |rows: std::ops::Range<usize>| {
+                rows.flat_map(|y| (0..width).map(move |x| (x, y)))
+                    .filter(|&(x, y)| shield.is_cleared(x, y))
+                    .count()
+            };
+
+            (count(0..height / 2), count(height / 2..height))
+        }
+
+        let mut world = World::default();
+        world.lasers.clear();
+
+        // Fire a bullet up into the solid left side of a shield
+        let shield = &world.shields[0];
+        let pos = Point::new(shield.pos.x + 3, shield.pos.y + shield.sprite.height() + 2);
+        world.fire_bullet(pos, false);
+        let hit = world.simulate_until(Controls::default(), 16, |world| world.bullets.is_empty());
+        assert!(hit, "Bullet should hit the shield");
+
+        let (top, bottom) = cleared(&world.shields[0]);
+        assert!(bottom > 0);
+        assert_eq!(top, 0, "Bullets erode the shield from below");
 
-        World::new(seed, false)
+        // Lasers erode from above
+        let shield = &mut world.shields[1];
+        shield.damage(&Point::new(3, 0), Heading::Down);
+        let (top, bottom) = cleared(shield);
+        assert!(top > 0);
+        assert_eq!(bottom, 0);
     }
-}
 
-impl Invaders {
-    /// Compute the bounding box for the Invader fleet.
-    ///
-    /// # Returns
-    ///
-    /// Tuple of `(top, right, bottom, left)`, e.g. in CSS clockwise order.
-    fn get_bounds(&self) -> (usize, usize, usize, usize) {
-        let width = (self.bounds.right_col - self.bounds.left_col + 1) * GRID.x;
-        let height = (self.bounds.bottom_row - self.bounds.top_row + 1) * GRID.y;
+    #[test]
+    fn test_laser_tempo() {
+        /// Count the lasers fired in a few seconds, after the grace period.
+        fn lasers_fired(world: &mut World) -> usize {
+            let mut fired = 0;
+            for _ in 0..FPS * 5 {
+                world.lasers.clear();
+                world.update(&Controls::default());
+                fired += world
+                    .events()
+                    .iter()
+                    .filter(|&&event| event == Event::LaserFired)
+                    .count();
+            }
 
-        let top = self.bounds.pos.y;
-        let bottom = top + height;
-        let left = self.bounds.pos.x;
-        let right = left + width;
+            fired
+        }
 
-        (top, right, bottom, left)
+        let mut full = World::default();
+        let mut cleared = World::default();
+        let cells = (0..ROWS - 1)
+            .flat_map(|row| (0..COLS).map(move |col| (col, row)))
+            .collect::<Vec<_>>();
+        cleared.with_cleared_cells(&cells);
+        assert!(cleared.invader_step_interval() < full.invader_step_interval());
+
+        let full = lasers_fired(&mut full);
+        let cleared = lasers_fired(&mut cleared);
+        assert!(
+            cleared > full,
+            "{} lasers fired by a nearly-cleared formation, {} by a full formation",
+            cleared,
+            full
+        );
     }
 
-    /// Resize the bounds to fit the live invaders.
-    ///
-    /// # Returns
-    ///
-    /// `true` when all invaders have been destroyed.
-    fn shrink_bounds(&mut self) -> bool {
-        let mut top = ROWS;
-        let mut right = 0;
-        let mut bottom = 0;
-        let mut left = COLS;
+    #[test]
+    fn test_powerups() {
+        let mut world = World::default();
+        assert_eq!(world.powerups().count(), 0);
 
-        // Scan through the entire grid
-        for (y, row) in self.grid.iter().enumerate() {
-            for (x, col) in row.iter().enumerate() {
-                if col.is_some() {
-                    // Build a boundary box of invaders in the grid
-                    if top > y {
-                        top = y;
-                    }
-                    if bottom < y {
-                        bottom = y;
-                    }
-                    if left > x {
-                        left = x;
-                    }
-                    if right < x {
-                        right = x;
-                    }
-                }
-            }
+        world.spawn_powerup(PowerUpKind::Burst, Point::new(40, 100));
+        assert_eq!(
+            world.powerups().collect::<Vec<_>>(),
+            [(PowerUpKind::Burst, 40, 100)]
+        );
+    }
+
+    #[test]
+    fn test_max_frames_per_update() {
+        let mut world = World::default();
+        world.set_max_frames_per_update(10);
+
+        // A long stall only runs up to the cap, and the excess time is discarded
+        let frames = world.advance(Duration::from_secs(3), &Controls::default());
+        assert_eq!(frames, 10);
+        assert_eq!(world.idle_time(), TIME_STEP * 10);
+
+        let frames = world.advance(TIME_STEP, &Controls::default());
+        assert_eq!(frames, 1);
+
+        // Partial time steps carry over
+        let half = TIME_STEP / 2;
+        assert_eq!(world.advance(half, &Controls::default()), 0);
+        assert_eq!(world.advance(half, &Controls::default()), 1);
+    }
+
+    #[test]
+    fn test_sudden_death() {
+        let normal = World::default();
+        let sudden_death = test_builder().mode(Mode::SuddenDeath).build();
+
+        assert_eq!(normal.lives(), LIVES);
+        assert_eq!(sudden_death.lives(), 1);
+        assert!(sudden_death.invader_step_interval() < normal.invader_step_interval());
+        assert!(sudden_death.laser_chance < normal.laser_chance);
+    }
+
+    #[test]
+    fn test_next_formation_preview() {
+        let mut world = World::default();
+        world.with_cleared_cells(&[(0, 0), (5, 2)]);
+        let preview = world.next_formation_preview();
+        assert_eq!(preview.len(), COLS * ROWS);
+        assert_eq!(world.invaders_remaining(), COLS * ROWS - 2);
+
+        // Clear the wave to advance to level 2
+        clear_invaders(&mut world);
+        assert_eq!(world.level(), 2);
+
+        let formation = world
+            .invaders
+            .grid
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|invader| {
+                let kind = invader.sprite.frame().invader_kind().unwrap();
+                (invader.pos.x, invader.pos.y, kind)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(preview, formation);
+        assert_eq!(preview[0].2, InvaderKind::Blipjoy);
+        assert_eq!(preview[COLS * ROWS - 1].2, InvaderKind::Cthulhu);
+    }
+
+    #[test]
+    fn test_last_invader_finale() {
+        let mut world = World::default();
+        clear_invaders_except(&mut world, (5, ROWS - 1));
+        assert_eq!(world.invaders_remaining(), 1);
+
+        for _ in 0..100 {
+            let before = world.invaders.grid[ROWS - 1][5].as_ref().unwrap().pos.x;
+            world.step_invaders();
+            let after = world.invaders.grid[ROWS - 1][5].as_ref().unwrap().pos.x;
+            assert!(before.abs_diff(after) > 2, "{} -> {}", before, after);
         }
+    }
 
-        if top > bottom || left > right {
-            // No more invaders left alive
-            return true;
+    #[test]
+    fn test_extends() {
+        let mut world = test_builder()
+            .score_table(ScoreTable {
+                extends: vec![200, 100, 100],
+                ..ScoreTable::default()
+            })
+            .build();
+
+        world.add_score(50);
+        assert_eq!(world.lives(), LIVES);
+
+        world.add_score(50);
+        assert_eq!(world.lives(), LIVES + 1);
+        assert_eq!(world.events(), [Event::ExtraLife(100)]);
+
+        // Each milestone is only awarded once
+        world.add_score(50);
+        assert_eq!(world.lives(), LIVES + 1);
+
+        world.add_score(500);
+        assert_eq!(world.lives(), LIVES + 2);
+        assert_eq!(
+            world.events(),
+            [Event::ExtraLife(100), Event::ExtraLife(200)]
+        );
+    }
+
+    #[test]
+    fn test_shield_damage_mask() {
+        let mut world = World::default();
+        world.shields[0].damage(&Point::new(5, 4), Heading::Down);
+        world.shields[2].damage(&Point::new(16, 9), Heading::Up);
+        world.shields[2].damage(&Point::new(3, 12), Heading::Up);
+
+        // The mask takes one bit per pixel
+        let masks = world.shield_damage();
+        let shield = &world.shields[0];
+        let pixels = shield.sprite.width() * shield.sprite.height();
+        assert_eq!(masks[0].len(), pixels / 8);
+
+        // Reapplying the masks to fresh shields reproduces the holes
+        let mut restored = World::default();
+        restored.with_shield_damage(&masks);
+        assert_eq!(
+            restored.shield_holes().collect::<Vec<_>>(),
+            world.shield_holes().collect::<Vec<_>>()
+        );
+        for (shield, restored) in world.shields.iter().zip(&restored.shields) {
+            assert_eq!(restored.sprite.pixels(), shield.sprite.pixels());
         }
+    }
 
-        // Adjust the bounding box position
-        self.bounds.pos.x += (left - self.bounds.left_col) * GRID.x;
-        self.bounds.pos.y += (top - self.bounds.top_row) * GRID.y;
+    #[test]
+    fn test_no_shields() {
+        let mut world = test_builder().shields(false).build();
+        world.lasers.clear();
+        assert_eq!(world.shield_holes().count(), 0);
 
-        // Adjust the bounding box columns and rows
-        self.bounds.left_col = left;
-        self.bounds.right_col = right;
-        self.bounds.top_row = top;
-        self.bounds.bottom_row = bottom;
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut screen);
+        let p1 = Point::new(0, 192);
+        let p2 = Point::new(WIDTH, 208);
+        assert!(!is_lit(&screen, WIDTH, p1, p2), "No shields are drawn");
 
-        // No more changes
-        false
+        // Lasers and bullets pass straight through the empty shield rows
+        for i in 0..4 {
+            spawn_laser(&mut world, Point::new(i * 45 + 40, 180));
+        }
+        world.fire_bullet(Point::new(40, 210), false);
+        world.fire_bullet(Point::new(175, 210), false);
+        for _ in 0..FPS {
+            world.update(&Controls::default());
+        }
+        assert!(world.lasers.iter().all(|laser| laser.pos.y < 180));
+        assert_eq!(world.shield_damage(), Vec::<Vec<u8>>::new());
     }
 
-    fn get_closest_invader(&self, mut col: usize) -> &Invader {
-        let mut row = ROWS - 1;
-        loop {
-            if self.grid[row][col].is_some() {
-                return self.grid[row][col].as_ref().unwrap();
-            }
+    #[test]
+    fn test_projectile_colors() {
+        /// Collect the colors of every lit pixel in a rectangle.
+        fn colors(screen: &[u8], rect: &Rect) -> Vec<[u8; 4]> {
+            (rect.p1.y..rect.p2.y)
+                .flat_map(|y| (rect.p1.x..rect.p2.x).map(move |x| (x + y * WIDTH) * 4))
+                .map(|i| [screen[i], screen[i + 1], screen[i + 2], screen[i + 3]])
+                .filter(|color| color[..3].iter().any(|&c| c > 0))
+                .collect()
+        }
 
-            if row == 0 {
-                row = ROWS - 1;
-                col += 1;
-                if col == COLS {
-                    col = 0;
-                }
-            } else {
-                row -= 1;
+        const GREEN: [u8; 4] = [0, 255, 0, 255];
+        const RED: [u8; 4] = [255, 0, 0, 255];
+
+        let mut world = World::default();
+        world.set_bullet_color(GREEN);
+        world.set_laser_color(RED);
+        world.fire_bullet(Point::new(20, 120), false);
+        spawn_laser(&mut world, Point::new(200, 120));
+
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut screen);
+
+        let bullet = &world.bullets[0];
+        let bullet = colors(&screen, &Rect::from_drawable(&bullet.pos, &bullet.sprite));
+        assert!(!bullet.is_empty());
+        assert!(bullet.iter().all(|&color| color == GREEN));
+
+        let laser = &world.lasers[0];
+        let laser = colors(&screen, &Rect::from_drawable(&laser.pos, &laser.sprite));
+        assert!(!laser.is_empty());
+        assert!(laser.iter().all(|&color| color == RED));
+    }
+
+    #[test]
+    fn test_shields() {
+        let mut world = World::default();
+        world.shields[1].damage(&Point::new(5, 4), Heading::Down);
+
+        let shields = world.shields().collect::<Vec<_>>();
+        assert_eq!(shields.len(), 4);
+        assert_eq!((shields[1].0, shields[1].1), (77, 192));
+        assert!(shields[1].2 > 0.0 && shields[1].2 < 1.0);
+        for (i, shield) in shields.iter().enumerate() {
+            if i != 1 {
+                assert_eq!(shield.2, 1.0);
+                assert!(shields[1].2 < shield.2);
             }
         }
     }
-}
 
-impl Default for Bounds {
-    fn default() -> Self {
-        Self {
-            pos: START,
-            left_col: 0,
-            right_col: COLS - 1,
-            top_row: 0,
-            bottom_row: ROWS - 1,
+    #[test]
+    fn test_state_hash() {
+        let build = |seed| WorldBuilder::new(seed).deterministic(true).build();
+        let mut world = build((1, 2));
+        let mut replay = build((1, 2));
+        let mut other = build((3, 4));
+        assert_eq!(world.state_hash(), replay.state_hash());
+
+        // Scripted inputs: weave back and forth while firing
+        for frame in 0..500 {
+            let controls = Controls {
+                direction: if frame / 60 % 2 == 0 {
+                    Direction::Left
+                } else {
+                    Direction::Right
+                },
+                fire: frame % 3 == 0,
+                pause: false,
+            };
+            world.update(&controls);
+            replay.update(&controls);
+            other.update(&controls);
         }
+
+        assert_eq!(world.state_hash(), replay.state_hash());
+        assert_ne!(world.state_hash(), other.state_hash());
     }
-}
 
-/// Clear the screen
-fn clear(screen: &mut [u8]) {
-    for (i, byte) in screen.iter_mut().enumerate() {
-        *byte = if i % 4 == 3 { 255 } else { 0 };
+    #[test]
+    #[should_panic(expected = "deterministic mode")]
+    fn test_deterministic_advance() {
+        let mut world = WorldBuilder::new((1, 2)).deterministic(true).build();
+
+        world.advance(TIME_STEP, &Controls::default());
     }
-}
 
-/// Create a grid of invaders.
-fn make_invader_grid(assets: &Assets) -> Vec<Vec<Option<Invader>>> {
-    use Frame::*;
+    #[test]
+    #[should_panic(expected = "deterministic mode")]
+    fn test_deterministic_random() {
+        let mut world = WorldBuilder::new((1, 2)).deterministic(true).build();
 
-    const BLIPJOY_OFFSET: Point = Point::new(3, 4);
-    const FERRIS_OFFSET: Point = Point::new(2, 5);
-    const CTHULHU_OFFSET: Point = Point::new(1, 3);
+        // Only the fixed time step may draw random numbers
+        world.update(&Controls::default());
+        world.random();
+    }
 
-    (0..1)
-        .map(|y| {
-            (0..COLS)
-                .map(|x| {
-                    Some(Invader {
-                        sprite: SpriteRef::new(assets, Blipjoy1, Duration::default()),
-                        pos: START + BLIPJOY_OFFSET + Point::new(x, y) * GRID,
-                        _score: 10,
-                    })
-                })
-                .collect()
-        })
-        .chain((1..3).map(|y| {
-            (0..COLS)
-                .map(|x| {
-                    Some(Invader {
-                        sprite: SpriteRef::new(assets, Ferris1, Duration::default()),
-                        pos: START + FERRIS_OFFSET + Point::new(x, y) * GRID,
-                        _score: 10,
-                    })
-                })
-                .collect()
-        }))
-        .chain((3..5).map(|y| {
-            (0..COLS)
+    #[test]
+    fn test_advance_matches_update() {
+        let controls = Controls {
+            direction: Direction::Left,
+            fire: true,
+            pause: false,
+        };
+
+        // The same total time produces the same state, however it is split up
+        let mut stepped = World::default();
+        for _ in 0..240 {
+            stepped.update(&controls);
+        }
+        let mut advanced = World::default();
+        for _ in 0..80 {
+            assert_eq!(advanced.advance(TIME_STEP * 3, &controls), 3);
+        }
+        assert_eq!(advanced.state_hash(), stepped.state_hash());
+    }
+
+    #[test]
+    fn test_knockback() {
+        let mut world = World::default();
+        world.set_knockback(true);
+
+        // The first hit knocks the player away from the laser, without costing a life
+        drop_laser_on_player(&mut world);
+        world.update(&Controls::default());
+        assert!(world.events().contains(&Event::PlayerKnockedBack));
+        assert_eq!(world.lives(), LIVES);
+        assert_eq!(world.player.pos.x, PLAYER_START.x + KNOCKBACK);
+
+        // Movement is ignored while stunned
+        let left = Controls {
+            direction: Direction::Left,
+            ..Controls::default()
+        };
+        let pos = world.player.pos;
+        let frames = STUN_DURATION.as_nanos() / TIME_STEP.as_nanos();
+        for _ in 0..frames {
+            world.lasers.clear();
+            world.update(&left);
+            assert_eq!(world.player.pos, pos);
+        }
+        world.simulate_until(left, 8, |world| world.player.pos.x < pos.x);
+        assert!(
+            world.player.pos.x < pos.x,
+            "Player should move after the stun"
+        );
+
+        // The next hit costs a life
+        drop_laser_on_player(&mut world);
+        world.update(&Controls::default());
+        assert!(world.events().contains(&Event::PlayerHit));
+        assert_eq!(world.lives(), LIVES - 1);
+    }
+
+    #[test]
+    fn test_baseline() {
+        let row_color = |screen: &[u8], y: usize| {
+            (0..WIDTH)
                 .map(|x| {
-                    Some(Invader {
-                        sprite: SpriteRef::new(assets, Cthulhu1, Duration::default()),
-                        pos: START + CTHULHU_OFFSET + Point::new(x, y) * GRID,
-                        _score: 10,
-                    })
+                    let i = (x + y * WIDTH) * 4;
+                    [screen[i], screen[i + 1], screen[i + 2], screen[i + 3]]
                 })
-                .collect()
-        }))
-        .collect()
-}
+                .collect::<Vec<_>>()
+        };
 
-fn next_invader<'a>(
-    invaders: &'a mut [Vec<Option<Invader>>],
-    stepper: &mut Point,
-) -> (&'a mut Invader, bool) {
-    let mut is_leader = false;
+        let mut world = World::default();
+        world.set_baseline(Some(240));
+        let mut screen = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut screen);
 
-    loop {
-        // Iterate through the entire grid
-        stepper.x += 1;
-        if stepper.x >= COLS {
-            stepper.x = 0;
-            if stepper.y == 0 {
-                stepper.y = ROWS - 1;
+        assert!(row_color(&screen, 240)
+            .iter()
+            .all(|&color| color == BASELINE_COLOR));
+        assert!(!is_lit(
+            &screen,
+            WIDTH,
+            Point::new(0, 241),
+            Point::new(WIDTH, HEIGHT)
+        ));
 
-                // After a full cycle, the next invader will be the leader
-                is_leader = true;
-            } else {
-                stepper.y -= 1;
+        // Invaders touching a raised ground end the game
+        world.set_baseline(Some(world.player.pos.y - 16));
+        let y = world.player.pos.y - 32;
+        descend_invaders_to(&mut world, y);
+        assert_eq!(world.outcome(), Some(Outcome::Invaded));
+
+        // The bottom of the play area is still on screen below the HUD strip
+        let mut world = test_builder().hud_height(HUD_HEIGHT).build();
+        world.set_baseline(Some(HEIGHT - HUD_HEIGHT - 1));
+        world.draw(&mut screen);
+        assert!(row_color(&screen, HEIGHT - 1)
+            .iter()
+            .all(|&color| color == BASELINE_COLOR));
+    }
+
+    #[test]
+    fn test_pause_toggles_on_press() {
+        let mut world = World::default();
+        let pause = Controls {
+            pause: true,
+            ..Controls::default()
+        };
+
+        // Holding pause only toggles once
+        for _ in 0..10 {
+            world.update(&pause);
+            assert!(world.is_paused());
+        }
+        let frozen = world.state_hash();
+        world.update(&pause);
+        assert_eq!(world.state_hash(), frozen);
+
+        // Releasing does not resume
+        world.update(&Controls::default());
+        assert!(world.is_paused());
+
+        // Pressing again resumes, and holding does not pause again
+        for _ in 0..10 {
+            world.update(&pause);
+            assert!(!world.is_paused());
+        }
+        assert_ne!(world.state_hash(), frozen);
+    }
+
+    #[test]
+    fn test_score_cap() {
+        let mut world = World::default();
+        world.set_score_cap(Some(999));
+        world.add_score(990);
+        world.add_score(30);
+        assert_eq!(world.score(), 20);
+        assert_eq!(world.high_score, 990);
+
+        let mut world = World::default();
+        world.set_score_cap(Some(999));
+        world.set_score_overflow(ScoreOverflow::Clamp);
+        world.add_score(990);
+        world.add_score(30);
+        assert_eq!(world.score(), 999);
+        world.add_score(30);
+        assert_eq!(world.score(), 999);
+
+        // Points beyond the range of the score keep counting
+        let mut world = test_builder().build();
+        world.points = u64::from(u32::MAX) - 10;
+        world.add_score(30);
+        assert_eq!(world.score(), u32::MAX);
+        world.set_score_cap(Some(999));
+        world.add_score(30);
+        assert_eq!(world.score(), ((u64::from(u32::MAX) + 50) % 1000) as u32);
+    }
+
+    #[test]
+    fn test_score_cap_extends() {
+        // Scoring at the cap never reaches a milestone above it
+        let mut world = test_builder()
+            .score_table(ScoreTable {
+                extends: vec![1500],
+                ..ScoreTable::default()
+            })
+            .build();
+        world.set_score_cap(Some(999));
+        world.set_score_overflow(ScoreOverflow::Clamp);
+        world.add_score(999);
+        world.add_score(600);
+        world.add_score(600);
+        assert_eq!(world.score(), 999);
+        assert_eq!(world.lives(), LIVES);
+
+        // Milestones below the cap are only awarded once, even after rolling over
+        let mut world = test_builder()
+            .score_table(ScoreTable {
+                extends: vec![500],
+                ..ScoreTable::default()
+            })
+            .build();
+        world.set_score_cap(Some(999));
+        world.add_score(600);
+        assert_eq!(world.lives(), LIVES + 1);
+        world.add_score(600);
+        world.add_score(300);
+        assert_eq!(world.score(), 500);
+        assert_eq!(world.lives(), LIVES + 1);
+    }
+
+    #[test]
+    fn test_render_list() {
+        let world = World::default();
+        let commands = world.render_list();
+        let layer = |z| commands.iter().filter(move |command| command.z == z);
+
+        assert_eq!(layer(Layer::Invaders).count(), COLS * ROWS);
+        assert_eq!(layer(Layer::Player).count(), 1);
+        let player = layer(Layer::Player).next().unwrap();
+        assert_eq!(player.pos, world.player.pos);
+        assert!(matches!(player.graphic, Graphic::Sprite { .. }));
+
+        let hud = layer(Layer::Hud)
+            .filter_map(|command| match &command.graphic {
+                Graphic::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(hud, ["SCORE 00000", "HI 00000", "LIVES 3 LV 1"]);
+
+        // Commands are in drawing order, with the HUD on top
+        assert!(commands.windows(2).all(|pair| pair[0].z <= pair[1].z));
+        assert_eq!(commands.last().unwrap().z, Layer::Hud);
+
+        // Sprites share their pixel data instead of copying it
+        match &player.graphic {
+            Graphic::Sprite { pixels, .. } => {
+                assert!(Rc::ptr_eq(pixels, &world.player.sprite.shared_pixels()));
             }
+            _ => unreachable!(),
         }
+    }
 
-        if invaders[stepper.y][stepper.x].is_some() {
-            return (invaders[stepper.y][stepper.x].as_mut().unwrap(), is_leader);
+    #[test]
+    fn test_render_list_matches_draw() {
+        let mut world = test_builder().hud_height(HUD_HEIGHT).build();
+        world.set_mirror(true);
+        world.set_baseline(Some(HEIGHT - HUD_HEIGHT - 1));
+        world.set_bullet_color([0, 255, 0, 255]);
+        world.fire_bullet(Point::new(20, 120), false);
+        world.spawn_ufo();
+
+        let mut drawn = vec![0; WIDTH * HEIGHT * 4];
+        world.draw(&mut drawn);
+
+        let mut composited = vec![0; WIDTH * HEIGHT * 4];
+        clear(&mut composited);
+        for command in world.render_list() {
+            command.draw(&mut composited);
+        }
+        assert!(
+            drawn == composited,
+            "Render list does not match the drawn frame"
+        );
+    }
+
+    #[test]
+    fn test_combo_bullet_color() {
+        const GOLD: [u8; 4] = [255, 200, 0, 255];
+
+        let bullet_tints = |world: &World| {
+            world
+                .render_list()
+                .into_iter()
+                .filter(|command| command.z == Layer::Projectiles)
+                .map(|command| command.tint)
+                .collect::<Vec<_>>()
+        };
+
+        let mut world = World::default();
+        world.set_combo_bullet_color(GOLD);
+        world.fire_bullet(Point::new(20, 120), false);
+        assert_eq!(bullet_tints(&world), [None]);
+        world.bullets.clear();
+
+        // Build a combo from two quick kills
+        shoot_invader(&mut world, 0, ROWS - 1);
+        shoot_invader(&mut world, 1, ROWS - 1);
+        assert_eq!(world.combo(), 2);
+        world.fire_bullet(Point::new(20, 120), false);
+        assert_eq!(bullet_tints(&world), [Some(GOLD)]);
+        world.bullets.clear();
+
+        // New bullets go back to normal once the combo expires
+        while world.combo() > 0 {
+            world.update(&Controls::default());
+        }
+        world.fire_bullet(Point::new(20, 120), false);
+        assert_eq!(bullet_tints(&world), [None]);
+    }
+
+    #[test]
+    fn test_invader_hp() {
+        let hp = [3, 2, 2, 1, 1];
+        let mut world = test_builder().invader_hp(hp).build();
+        let grid_hp = |world: &World| {
+            world
+                .invaders
+                .grid
+                .iter()
+                .map(|row| row.iter().flatten().map(|invader| invader.hp).collect())
+                .collect::<Vec<Vec<_>>>()
+        };
+        let expected = hp.iter().map(|&hp| vec![hp; COLS]).collect::<Vec<_>>();
+        assert_eq!(grid_hp(&world), expected);
+
+        // Every wave uses the same hit points
+        clear_invaders(&mut world);
+        assert_eq!(world.level(), 2);
+        assert_eq!(grid_hp(&world), expected);
+    }
+
+    #[test]
+    fn test_rewind_disabled() {
+        let mut world = World::default();
+        for _ in 0..10 {
+            world.update(&Controls::default());
         }
+        assert!(world.snapshots.is_empty());
+
+        let hash = world.state_hash();
+        world.rewind(5);
+        assert_eq!(world.state_hash(), hash);
+    }
+
+    #[test]
+    fn test_rewind_pause() {
+        let mut world = test_builder().rewind(true).build();
+        let pause = Controls {
+            pause: true,
+            ..Controls::default()
+        };
+        let idle_frames = 10;
+        for _ in 0..idle_frames {
+            world.update(&Controls::default());
+        }
+        let idle_time = world.idle_time();
+
+        world.update(&pause);
+        world.update(&pause);
+        assert!(world.is_paused());
+
+        // Rewinding before the press unpauses, and the next press pauses again
+        world.rewind(2);
+        assert!(!world.is_paused());
+        assert_eq!(world.idle_time(), idle_time);
+        world.update(&pause);
+        assert!(world.is_paused());
+
+        // Rewinding while the button is held does not toggle the pause again
+        world.update(&pause);
+        world.rewind(1);
+        world.update(&pause);
+        assert!(world.is_paused());
     }
 }
@@ -0,0 +1,14 @@
+//! Game mode presets.
+
+/// Presets that combine difficulty settings into a distinct way to play.
+///
+/// See [`WorldBuilder::mode`](crate::WorldBuilder::mode).
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Mode {
+    /// The classic game.
+    #[default]
+    Normal,
+    /// A short, intense game: the player has a single life, the invaders march twice as fast,
+    /// and they fire lasers twice as often.
+    SuddenDeath,
+}
@@ -0,0 +1,52 @@
+//! Color palettes for tinting the play field.
+
+/// The colors used to tint each kind of entity.
+///
+/// Sprites are tinted at blit time by multiplying each color channel. The sprites are white, so
+/// they are drawn in exactly the tint color.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Palette {
+    /// Tint for the invader fleet.
+    pub invaders: [u8; 4],
+    /// Tint for the shields.
+    pub shields: [u8; 4],
+    /// Tint for the player's tank.
+    pub player: [u8; 4],
+}
+
+// Palettes are cycled in this order, one per level
+const PALETTES: [Palette; 4] = [
+    // Classic monochrome
+    Palette {
+        invaders: [255, 255, 255, 255],
+        shields: [255, 255, 255, 255],
+        player: [255, 255, 255, 255],
+    },
+    // Toxic
+    Palette {
+        invaders: [64, 255, 64, 255],
+        shields: [255, 224, 64, 255],
+        player: [64, 224, 255, 255],
+    },
+    // Ember
+    Palette {
+        invaders: [255, 96, 32, 255],
+        shields: [160, 160, 255, 255],
+        player: [255, 255, 160, 255],
+    },
+    // Deep space
+    Palette {
+        invaders: [192, 96, 255, 255],
+        shields: [64, 255, 192, 255],
+        player: [255, 160, 224, 255],
+    },
+];
+
+/// Get the palette for a level.
+///
+/// Levels start at 1. Palettes are cycled when the level exceeds the number of palettes.
+pub fn level_palette(level: u32) -> Palette {
+    let i = level.saturating_sub(1) as usize % PALETTES.len();
+
+    PALETTES[i]
+}
@@ -0,0 +1,68 @@
+//! Power-ups dropped by destroyed invaders.
+
+use crate::geo::Point;
+use crate::sprites::Sprite;
+use crate::{DeltaTime, ONE_FRAME};
+use std::time::Duration;
+
+// Power-ups are drawn as a small diamond
+const SIZE: usize = 5;
+
+/// The kinds of power-ups that the player can collect.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum PowerUpKind {
+    /// Immediately fires a vertical volley of bullets.
+    Burst,
+}
+
+/// The power-up entity.
+#[derive(Clone, Debug, Hash)]
+pub(crate) struct PowerUp {
+    pub(crate) kind: PowerUpKind,
+    pub(crate) sprite: Sprite,
+    pub(crate) pos: Point,
+    dt: Duration,
+}
+
+impl PowerUpKind {
+    /// The color used to draw this kind of power-up.
+    fn color(self) -> [u8; 4] {
+        match self {
+            PowerUpKind::Burst => [255, 255, 0, 255],
+        }
+    }
+}
+
+impl PowerUp {
+    pub(crate) fn new(kind: PowerUpKind, pos: Point) -> PowerUp {
+        PowerUp {
+            kind,
+            sprite: make_sprite(kind.color()),
+            pos,
+            dt: Duration::default(),
+        }
+    }
+}
+
+impl DeltaTime for PowerUp {
+    fn update(&mut self) -> usize {
+        Self::update_dt(&mut self.dt, ONE_FRAME)
+    }
+}
+
+/// Procedurally generate a diamond-shaped sprite.
+fn make_sprite(color: [u8; 4]) -> Sprite {
+    let center = SIZE / 2;
+    let pixels = (0..SIZE)
+        .flat_map(|y| (0..SIZE).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            if center.abs_diff(x) + center.abs_diff(y) <= center {
+                color
+            } else {
+                [0, 0, 0, 255]
+            }
+        })
+        .collect();
+
+    Sprite::from_pixels(SIZE, SIZE, pixels)
+}
@@ -0,0 +1,188 @@
+//! Draw commands for external renderers.
+
+use crate::geo::Point;
+use crate::sprites::{blit, blit_flipped, blit_tinted, line, rect, Drawable};
+use crate::text::draw_text;
+use crate::{HEIGHT, WIDTH};
+use std::rc::Rc;
+
+// Graphics without a tint are drawn in white, except for sprites
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+/// Something to draw, with everything needed to composite it.
+///
+/// See [`World::render_list`](crate::World::render_list).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DrawCommand {
+    /// Top-left corner in screen coordinates, or the start point of a line.
+    pub pos: Point,
+    /// What to draw.
+    pub graphic: Graphic,
+    /// Commands in higher layers are drawn over lower layers.
+    pub z: Layer,
+    /// Multiply each color channel of every lit sprite pixel by this color, if any. The sprites
+    /// are white, so they are drawn in exactly this color. Text, lines, and outlines are drawn in
+    /// this color.
+    pub tint: Option<[u8; 4]>,
+}
+
+/// The kinds of graphics in a [`DrawCommand`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Graphic {
+    /// An RGBA image. Transparent pixels have an alpha of 0.
+    Sprite {
+        /// Width of the image in pixels.
+        width: usize,
+        /// Height of the image in pixels.
+        height: usize,
+        /// Pixel data, `width * height * 4` bytes.
+        pixels: Rc<[u8]>,
+        /// Draw the image mirrored horizontally.
+        flipped: bool,
+    },
+    /// A string in the HUD font.
+    Text(String),
+    /// A one pixel wide line to this end point (inclusive).
+    Line(Point),
+    /// A one pixel wide rectangle outline.
+    Outline {
+        /// Width of the rectangle in pixels.
+        width: usize,
+        /// Height of the rectangle in pixels.
+        height: usize,
+    },
+}
+
+/// Draw order for [`DrawCommand`]s, from bottom to top.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Layer {
+    /// The invader fleet, including dying invaders.
+    Invaders,
+    /// The shields.
+    Shields,
+    /// The player's tank.
+    Player,
+    /// Bullets and lasers.
+    Projectiles,
+    /// The bonus UFO.
+    Ufo,
+    /// Falling power-ups.
+    PowerUps,
+    /// The ground line.
+    Ground,
+    /// The last life warning.
+    Warning,
+    /// The HUD and the wave countdown.
+    Hud,
+}
+
+impl DrawCommand {
+    /// Create a command to draw a sprite.
+    pub(crate) fn sprite<D>(pos: Point, drawable: &D, z: Layer, tint: Option<[u8; 4]>) -> Self
+    where
+        D: Drawable,
+    {
+        let graphic = Graphic::Sprite {
+            width: drawable.width(),
+            height: drawable.height(),
+            pixels: drawable.shared_pixels(),
+            flipped: false,
+        };
+
+        Self {
+            pos,
+            graphic,
+            z,
+            tint,
+        }
+    }
+
+    /// Draw the command to a screen-sized pixel buffer.
+    pub(crate) fn draw(&self, screen: &mut [u8]) {
+        let color = self.tint.unwrap_or(WHITE);
+
+        match &self.graphic {
+            Graphic::Sprite {
+                width,
+                height,
+                pixels,
+                flipped,
+            } => {
+                let image = Image {
+                    width: *width,
+                    height: *height,
+                    pixels: Rc::clone(pixels),
+                };
+                match (flipped, self.tint) {
+                    (true, tint) => blit_flipped(screen, &self.pos, &image, tint),
+                    (false, Some(tint)) => blit_tinted(screen, &self.pos, &image, tint),
+                    (false, None) => blit(screen, &self.pos, &image),
+                }
+            }
+            Graphic::Text(text) => draw_text(screen, WIDTH, HEIGHT, &self.pos, text, color),
+            Graphic::Line(end) => line(screen, &self.pos, end, color),
+            Graphic::Outline { width, height } => {
+                let p2 = Point::new(self.pos.x + width, self.pos.y + height);
+                rect(screen, &self.pos, &p2, color);
+            }
+        }
+    }
+
+    /// Mirror the command horizontally across the screen.
+    ///
+    /// Text is left in place, so it remains readable.
+    pub(crate) fn mirrored(mut self) -> Self {
+        match &mut self.graphic {
+            Graphic::Sprite { width, flipped, .. } => {
+                self.pos.x = WIDTH - self.pos.x - *width;
+                *flipped = !*flipped;
+            }
+            Graphic::Text(_) => (),
+            Graphic::Line(end) => {
+                let (x1, x2) = (WIDTH - 1 - self.pos.x, WIDTH - 1 - end.x);
+                self.pos.x = x1;
+                end.x = x2;
+            }
+            Graphic::Outline { width, .. } => {
+                self.pos.x = WIDTH - self.pos.x - *width;
+            }
+        }
+
+        self
+    }
+
+    /// Move the command down the screen.
+    pub(crate) fn offset(mut self, y: usize) -> Self {
+        self.pos.y += y;
+        if let Graphic::Line(end) = &mut self.graphic {
+            end.y += y;
+        }
+
+        self
+    }
+}
+
+/// The pixels of a sprite command, for blitting.
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Rc<[u8]>,
+}
+
+impl Drawable for Image {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    fn shared_pixels(&self) -> Rc<[u8]> {
+        Rc::clone(&self.pixels)
+    }
+}
@@ -0,0 +1,45 @@
+//! Configurable scoring rules.
+
+use crate::ROWS;
+
+/// Points awarded for everything the player can score from.
+///
+/// The default table uses the arcade values.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ScoreTable {
+    /// Points for destroying an invader, indexed by formation row from top to bottom.
+    pub rows: [u32; ROWS],
+    /// Possible bonuses for destroying the UFO. One is chosen at random for each UFO.
+    pub ufo_bonus: Vec<u32>,
+    /// Bonus for clearing a wave.
+    pub wave_bonus: u32,
+    /// Additional bonus for clearing a wave without losing a life or taking shield damage.
+    pub perfect_bonus: u32,
+    /// Score milestones that each award an extra life, once, even if listed more than once. The
+    /// default table has none.
+    pub extends: Vec<u32>,
+}
+
+/// What happens when the score exceeds the cap set with
+/// [`World::set_score_cap`](crate::World::set_score_cap).
+///
+/// Chosen with [`World::set_score_overflow`](crate::World::set_score_overflow).
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ScoreOverflow {
+    /// The score wraps around past zero, like the arcade counter.
+    Rollover,
+    /// The score stays at the cap.
+    Clamp,
+}
+
+impl Default for ScoreTable {
+    fn default() -> Self {
+        Self {
+            rows: [30, 20, 20, 10, 10],
+            ufo_bonus: vec![50, 100, 150, 300],
+            wave_bonus: 0,
+            perfect_bonus: 1000,
+            extends: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Lightweight snapshots of the logical game state, for rewinding.
+
+use crate::palette::Palette;
+use crate::powerups::PowerUp;
+use crate::state::{GameState, Outcome};
+use crate::ufo::Ufo;
+use crate::{Bullet, Controls, Ghost, Invaders, Laser, Player, Shield, World};
+use randomize::PCG32;
+use std::time::Duration;
+
+/// Everything that changes while the game is played.
+///
+/// Assets and settings are not included, since they are shared by every snapshot.
+#[derive(Clone, Debug, Hash)]
+pub(crate) struct Snapshot {
+    invaders: Invaders,
+    lasers: Vec<Laser>,
+    shields: Vec<Shield>,
+    player: Player,
+    bullets: Vec<Bullet>,
+    powerups: Vec<PowerUp>,
+    ufo: Option<Ufo>,
+    ufo_from_left: bool,
+    ghosts: Vec<Ghost>,
+    score: u32,
+    points: u64,
+    high_score: u32,
+    lives: u8,
+    warning_dt: Duration,
+    level: u32,
+    damage_taken_this_wave: bool,
+    palette: Palette,
+    dt: Duration,
+    countdown: Duration,
+    grace: Duration,
+    charge_timer: Duration,
+    bob_dt: Duration,
+    knockback_ready: bool,
+    stun_timer: Duration,
+    combo: u32,
+    combo_timer: Duration,
+    idle_time: Duration,
+    accumulator: Duration,
+    prev_controls: Controls,
+    paused: bool,
+    state: GameState,
+    outcome: Option<Outcome>,
+    prng: PCG32,
+    bonus_prng: PCG32,
+}
+
+impl Snapshot {
+    /// Capture the logical state of a `World`.
+    pub(crate) fn new(world: &World) -> Snapshot {
+        Snapshot {
+            invaders: world.invaders.clone(),
+            lasers: world.lasers.clone(),
+            shields: world.shields.clone(),
+            player: world.player.clone(),
+            bullets: world.bullets.clone(),
+            powerups: world.powerups.clone(),
+            ufo: world.ufo.clone(),
+            ufo_from_left: world.ufo_from_left,
+            ghosts: world.ghosts.clone(),
+            score: world.score,
+            points: world.points,
+            high_score: world.high_score,
+            lives: world.lives,
+            warning_dt: world.warning_dt,
+            level: world.level,
+            damage_taken_this_wave: world.damage_taken_this_wave,
+            palette: world.palette,
+            dt: world.dt,
+            countdown: world.countdown,
+            grace: world.grace,
+            charge_timer: world.charge_timer,
+            bob_dt: world.bob_dt,
+            knockback_ready: world.knockback_ready,
+            stun_timer: world.stun_timer,
+            combo: world.combo,
+            combo_timer: world.combo_timer,
+            idle_time: world.idle_time,
+            accumulator: world.accumulator,
+            prev_controls: world.prev_controls,
+            paused: world.paused,
+            state: world.state,
+            outcome: world.outcome,
+            prng: world.prng.clone(),
+            bonus_prng: world.bonus_prng.clone(),
+        }
+    }
+
+    /// Replace the logical state of a `World` with this snapshot.
+    pub(crate) fn restore(self, world: &mut World) {
+        world.invaders = self.invaders;
+        world.lasers = self.lasers;
+        world.shields = self.shields;
+        world.player = self.player;
+        world.bullets = self.bullets;
+        world.powerups = self.powerups;
+        world.ufo = self.ufo;
+        world.ufo_from_left = self.ufo_from_left;
+        world.ghosts = self.ghosts;
+        world.score = self.score;
+        world.points = self.points;
+        world.high_score = self.high_score;
+        world.lives = self.lives;
+        world.warning_dt = self.warning_dt;
+        world.level = self.level;
+        world.damage_taken_this_wave = self.damage_taken_this_wave;
+        world.palette = self.palette;
+        world.dt = self.dt;
+        world.countdown = self.countdown;
+        world.grace = self.grace;
+        world.charge_timer = self.charge_timer;
+        world.bob_dt = self.bob_dt;
+        world.knockback_ready = self.knockback_ready;
+        world.stun_timer = self.stun_timer;
+        world.combo = self.combo;
+        world.combo_timer = self.combo_timer;
+        world.idle_time = self.idle_time;
+        world.accumulator = self.accumulator;
+        world.prev_controls = self.prev_controls;
+        world.paused = self.paused;
+        world.state = self.state;
+        world.outcome = self.outcome;
+        world.prng = self.prng;
+        world.bonus_prng = self.bonus_prng;
+    }
+}
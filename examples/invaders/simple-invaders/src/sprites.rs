@@ -1,6 +1,6 @@
 use crate::loader::Assets;
 use crate::TIME_STEP;
-use crate::{Point, HEIGHT, WIDTH};
+use crate::{Point, Rect, HEIGHT, WIDTH};
 use line_drawing::Bresenham;
 use std::cmp::min;
 use std::rc::Rc;
@@ -9,8 +9,19 @@ use std::time::Duration;
 // This is the type stored in the `Assets` hash map
 pub(crate) type CachedSprite = (usize, usize, Rc<[u8]>);
 
+/// The kinds of invaders in the formation.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum InvaderKind {
+    /// The invaders in the top row.
+    Blipjoy,
+    /// The invaders in the middle rows.
+    Ferris,
+    /// The invaders in the bottom rows.
+    Cthulhu,
+}
+
 /// Frame identifier for managing animations.
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub(crate) enum Frame {
     Blipjoy1,
     Blipjoy2,
@@ -42,21 +53,37 @@ pub(crate) enum Frame {
     Laser8,
 }
 
+impl Frame {
+    /// The kind of invader drawn by this frame, if any.
+    pub(crate) fn invader_kind(&self) -> Option<InvaderKind> {
+        use Frame::*;
+
+        match self {
+            Blipjoy1 | Blipjoy2 => Some(InvaderKind::Blipjoy),
+            Ferris1 | Ferris2 => Some(InvaderKind::Ferris),
+            Cthulhu1 | Cthulhu2 => Some(InvaderKind::Cthulhu),
+            _ => None,
+        }
+    }
+}
+
 /// Sprites can be drawn and procedurally generated.
 ///
-/// A `Sprite` owns its pixel data, and cannot be animated. Use a `SpriteRef` if you need
+/// A `Sprite` can modify its pixel data, and cannot be animated. Use a `SpriteRef` if you need
 /// animations.
-#[derive(Debug)]
+///
+/// The pixel data is copied on the first write, so sprites are cheap to clone.
+#[derive(Clone, Debug, Hash)]
 pub(crate) struct Sprite {
     width: usize,
     height: usize,
-    pixels: Vec<u8>,
+    pixels: Rc<[u8]>,
 }
 
 /// SpriteRefs can be drawn and animated.
 ///
 /// They reference their pixel data (instead of owning it).
-#[derive(Debug)]
+#[derive(Clone, Debug, Hash)]
 pub(crate) struct SpriteRef {
     width: usize,
     height: usize,
@@ -71,6 +98,7 @@ pub(crate) trait Drawable {
     fn width(&self) -> usize;
     fn height(&self) -> usize;
     fn pixels(&self) -> &[u8];
+    fn shared_pixels(&self) -> Rc<[u8]>;
 }
 
 pub(crate) trait Animation {
@@ -84,8 +112,71 @@ impl Sprite {
         Sprite {
             width: *width,
             height: *height,
-            pixels: pixels.to_vec(),
+            pixels: Rc::clone(pixels),
+        }
+    }
+
+    /// Create a procedurally generated sprite from raw RGBA pixels.
+    pub(crate) fn from_pixels(width: usize, height: usize, pixels: Vec<u8>) -> Sprite {
+        assert_eq!(pixels.len(), width * height * 4);
+
+        Sprite {
+            width,
+            height,
+            pixels: Rc::from(pixels),
+        }
+    }
+
+    /// Test whether the pixel at the given coordinates is drawn.
+    ///
+    /// Black pixels are never drawn by [`blit`], so they are considered transparent.
+    pub(crate) fn is_lit(&self, x: usize, y: usize) -> bool {
+        let i = (x + y * self.width) * 4;
+
+        self.pixels[i..i + 3].iter().any(|&c| c > 0)
+    }
+
+    /// Create a new sprite from a sub-rectangle of this sprite's pixels, e.g. to extract glyphs
+    /// from a font sheet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rectangle is not within the bounds of this sprite.
+    pub(crate) fn crop(&self, rect: Rect) -> Sprite {
+        assert!(
+            rect.p1.x <= rect.p2.x && rect.p1.y <= rect.p2.y,
+            "Crop rectangle is inverted"
+        );
+        assert!(
+            rect.p2.x <= self.width && rect.p2.y <= self.height,
+            "Crop rectangle is out of bounds"
+        );
+
+        let width = rect.p2.x - rect.p1.x;
+        let height = rect.p2.y - rect.p1.y;
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for y in rect.p1.y..rect.p2.y {
+            let i = (rect.p1.x + y * self.width) * 4;
+            pixels.extend_from_slice(&self.pixels[i..i + width * 4]);
+        }
+
+        Sprite::from_pixels(width, height, pixels)
+    }
+
+    /// Clear the pixel at the given coordinates, making it transparent.
+    pub(crate) fn clear(&mut self, x: usize, y: usize) {
+        let i = (x + y * self.width) * 4;
+
+        self.pixels_mut()[i..i + 3].fill(0);
+    }
+
+    /// Get the pixel data for writing, copying it first if it is shared.
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        if Rc::get_mut(&mut self.pixels).is_none() {
+            self.pixels = Rc::from(&self.pixels[..]);
         }
+
+        Rc::get_mut(&mut self.pixels).unwrap()
     }
 }
 
@@ -103,6 +194,11 @@ impl SpriteRef {
         }
     }
 
+    /// The animation frame currently referenced.
+    pub(crate) fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
     pub(crate) fn step_frame(&mut self, assets: &Assets) {
         use Frame::*;
 
@@ -155,6 +251,10 @@ impl Drawable for Sprite {
     fn pixels(&self) -> &[u8] {
         &self.pixels
     }
+
+    fn shared_pixels(&self) -> Rc<[u8]> {
+        Rc::clone(&self.pixels)
+    }
 }
 
 impl Drawable for SpriteRef {
@@ -169,6 +269,10 @@ impl Drawable for SpriteRef {
     fn pixels(&self) -> &[u8] {
         &self.pixels
     }
+
+    fn shared_pixels(&self) -> Rc<[u8]> {
+        Rc::clone(&self.pixels)
+    }
 }
 
 impl Animation for SpriteRef {
@@ -213,6 +317,96 @@ where
     }
 }
 
+/// Blit a drawable to the pixel buffer, mirrored horizontally, and optionally tinted like
+/// [`blit_tinted`].
+pub(crate) fn blit_flipped<S>(screen: &mut [u8], dest: &Point, sprite: &S, tint: Option<[u8; 4]>)
+where
+    S: Drawable,
+{
+    assert!(dest.x + sprite.width() <= WIDTH);
+    assert!(dest.y + sprite.height() <= HEIGHT);
+
+    let pixels = sprite.pixels();
+    let width = sprite.width() * 4;
+
+    let mut s = 0;
+    for y in 0..sprite.height() {
+        let i = dest.x * 4 + dest.y * WIDTH * 4 + y * WIDTH * 4;
+
+        // Merge pixels from sprite into screen, reading each row backwards
+        let zipped = screen[i..i + width]
+            .chunks_exact_mut(4)
+            .zip(pixels[s..s + width].chunks_exact(4).rev());
+        for (left, right) in zipped {
+            match tint {
+                Some(tint) => tint_pixel(left, right, tint),
+                None => {
+                    for (left, &right) in left.iter_mut().zip(right) {
+                        if right > 0 {
+                            *left = right;
+                        }
+                    }
+                }
+            }
+        }
+
+        s += width;
+    }
+}
+
+/// Blit a drawable to the pixel buffer, multiplied by a tint.
+///
+/// Each color channel of a lit sprite pixel is multiplied by the same channel of the tint, so a
+/// white pixel takes the tint color and a black pixel is skipped.
+pub(crate) fn blit_tinted<S>(screen: &mut [u8], dest: &Point, sprite: &S, tint: [u8; 4])
+where
+    S: Drawable,
+{
+    assert!(dest.x + sprite.width() <= WIDTH);
+    assert!(dest.y + sprite.height() <= HEIGHT);
+
+    let pixels = sprite.pixels();
+    let width = sprite.width() * 4;
+
+    let mut s = 0;
+    for y in 0..sprite.height() {
+        let i = dest.x * 4 + dest.y * WIDTH * 4 + y * WIDTH * 4;
+
+        // Merge tinted pixels from sprite into screen
+        let zipped = screen[i..i + width]
+            .chunks_exact_mut(4)
+            .zip(pixels[s..s + width].chunks_exact(4));
+        for (left, right) in zipped {
+            tint_pixel(left, right, tint);
+        }
+
+        s += width;
+    }
+}
+
+/// Mirror the entire pixel buffer horizontally.
+pub(crate) fn mirror(screen: &mut [u8]) {
+    for row in screen.chunks_exact_mut(WIDTH * 4) {
+        for x in 0..WIDTH / 2 {
+            let left = x * 4;
+            let right = (WIDTH - 1 - x) * 4;
+            for c in 0..4 {
+                row.swap(left + c, right + c);
+            }
+        }
+    }
+}
+
+/// Merge one RGBA sprite pixel into the screen, multiplied by a tint. Black pixels are skipped.
+fn tint_pixel(left: &mut [u8], right: &[u8], tint: [u8; 4]) {
+    if right[..3].iter().any(|&c| c > 0) {
+        for c in 0..3 {
+            left[c] = (right[c] as u16 * tint[c] as u16 / 255) as u8;
+        }
+        left[3] = right[3];
+    }
+}
+
 /// Draw a line to the pixel buffer using Bresenham's algorithm.
 pub(crate) fn line(screen: &mut [u8], p1: &Point, p2: &Point, color: [u8; 4]) {
     let p1 = (p1.x as i64, p1.y as i64);
@@ -238,3 +432,33 @@ pub(crate) fn rect(screen: &mut [u8], p1: &Point, p2: &Point, color: [u8; 4]) {
     line(screen, &p2, &p4, color);
     line(screen, &p4, p1, color);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crop() {
+        // Each pixel stores its own index in every channel
+        let pixels = (0..16_u8).flat_map(|i| [i; 4]).collect();
+        let sprite = Sprite::from_pixels(4, 4, pixels);
+
+        let cropped = sprite.crop(Rect::new(&Point::new(1, 1), &Point::new(3, 3)));
+        assert_eq!(cropped.width, 2);
+        assert_eq!(cropped.height, 2);
+
+        let expected = [5_u8, 6, 9, 10]
+            .iter()
+            .flat_map(|&i| [i; 4])
+            .collect::<Vec<_>>();
+        assert_eq!(&cropped.pixels[..], &expected[..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_crop_out_of_bounds() {
+        let sprite = Sprite::from_pixels(4, 4, vec![0; 4 * 4 * 4]);
+
+        sprite.crop(Rect::new(&Point::new(2, 2), &Point::new(5, 3)));
+    }
+}
@@ -0,0 +1,41 @@
+//! High-level game state.
+
+/// The overall state of the game.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum GameState {
+    /// The game is in progress.
+    Playing,
+    /// The game has ended, and the world no longer updates.
+    GameOver,
+}
+
+/// How the game ended.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Outcome {
+    /// The player cleared the final wave. See
+    /// [`WorldBuilder::final_level`](crate::WorldBuilder::final_level).
+    Won,
+    /// The player was hit by a laser on their last life.
+    LostAllLives,
+    /// The invaders reached the player.
+    Invaded,
+}
+
+/// A snapshot of commonly needed read-only values.
+///
+/// See [`World::stats`](crate::World::stats).
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct GameStats {
+    /// The player's current score.
+    pub score: u32,
+    /// The highest score reached.
+    pub high_score: u32,
+    /// The number of lives the player has remaining.
+    pub lives: u8,
+    /// The current level, starting at 1.
+    pub level: u32,
+    /// The number of invaders still alive in the current wave.
+    pub invaders_remaining: usize,
+    /// The overall state of the game.
+    pub state: GameState,
+}
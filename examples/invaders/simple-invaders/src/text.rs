@@ -0,0 +1,143 @@
+//! A tiny bitmap font for drawing text to the pixel buffer.
+
+use crate::geo::{Point, Rect};
+use crate::sprites::Sprite;
+
+/// Glyph width in pixels.
+pub(crate) const GLYPH_WIDTH: usize = 3;
+/// Glyph height in pixels.
+pub(crate) const GLYPH_HEIGHT: usize = 5;
+/// Horizontal distance between the start of two consecutive glyphs.
+pub(crate) const GLYPH_ADVANCE: usize = GLYPH_WIDTH + 1;
+
+// Every character in the font, in font sheet order. Unknown characters use the trailing space.
+const CHARSET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-:! ";
+
+thread_local! {
+    // The font sheet is only built once
+    static FONT_SHEET: Sprite = font_sheet();
+}
+
+/// Compute the width of a string in pixels.
+pub(crate) fn text_width(text: &str) -> usize {
+    let len = text.chars().count();
+
+    if len == 0 {
+        0
+    } else {
+        len * GLYPH_ADVANCE - 1
+    }
+}
+
+/// Draw a string to a pixel buffer with the given dimensions.
+///
+/// Glyphs that fall outside of the buffer are clipped. Unknown characters are drawn as spaces.
+pub(crate) fn draw_text(
+    screen: &mut [u8],
+    width: usize,
+    height: usize,
+    pos: &Point,
+    text: &str,
+    color: [u8; 4],
+) {
+    for (i, c) in text.chars().enumerate() {
+        let glyph = FONT_SHEET.with(|sheet| sheet.crop(glyph_rect(c)));
+        let left = pos.x + i * GLYPH_ADVANCE;
+
+        for y in 0..GLYPH_HEIGHT {
+            let sy = pos.y + y;
+            if sy >= height {
+                break;
+            }
+
+            for x in 0..GLYPH_WIDTH {
+                let sx = left + x;
+                if sx < width && glyph.is_lit(x, y) {
+                    let i = (sx + sy * width) * 4;
+                    screen[i..i + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+/// Render every glyph in [`CHARSET`] side by side, in white.
+fn font_sheet() -> Sprite {
+    let width = CHARSET.len() * GLYPH_WIDTH;
+    let mut pixels = vec![0; width * GLYPH_HEIGHT * 4];
+
+    for (i, c) in CHARSET.chars().enumerate() {
+        for (y, bits) in glyph(c).iter().enumerate() {
+            for x in 0..GLYPH_WIDTH {
+                let mask = 1 << (GLYPH_WIDTH - 1 - x);
+                if bits & mask != 0 {
+                    let j = (i * GLYPH_WIDTH + x + y * width) * 4;
+                    pixels[j..j + 4].copy_from_slice(&[255; 4]);
+                }
+            }
+        }
+    }
+
+    Sprite::from_pixels(width, GLYPH_HEIGHT, pixels)
+}
+
+/// Find the area of the font sheet that holds a character.
+fn glyph_rect(c: char) -> Rect {
+    let c = c.to_ascii_uppercase();
+    let i = CHARSET.find(c).unwrap_or_else(|| CHARSET.len() - 1);
+    let p1 = Point::new(i * GLYPH_WIDTH, 0);
+    let p2 = Point::new(p1.x + GLYPH_WIDTH, GLYPH_HEIGHT);
+
+    Rect::new(&p1, &p2)
+}
+
+/// Get the bitmap for a single character.
+///
+/// Each row is stored in the low 3 bits, with the most significant bit on the left.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b011, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+
+        _ => [0; GLYPH_HEIGHT],
+    }
+}
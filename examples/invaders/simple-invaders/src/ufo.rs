@@ -0,0 +1,58 @@
+//! The bonus ship that occasionally flies across the top of the screen.
+
+use crate::controls::Direction;
+use crate::geo::Point;
+use crate::sprites::Sprite;
+use crate::{DeltaTime, ONE_FRAME};
+use std::time::Duration;
+
+// The UFO is drawn procedurally, facing right
+const SPRITE: [&str; 7] = [
+    "........####....",
+    "...##########...",
+    "..############..",
+    ".##.##.##.##.##.",
+    "################",
+    "..###..##..###..",
+    "...#........#...",
+];
+const COLOR: [u8; 4] = [255, 64, 64, 255];
+
+/// The UFO entity.
+#[derive(Clone, Debug, Hash)]
+pub(crate) struct Ufo {
+    pub(crate) sprite: Sprite,
+    pub(crate) pos: Point,
+    pub(crate) direction: Direction,
+    dt: Duration,
+}
+
+impl Ufo {
+    pub(crate) fn new(pos: Point, direction: Direction) -> Ufo {
+        Ufo {
+            sprite: make_sprite(),
+            pos,
+            direction,
+            dt: Duration::default(),
+        }
+    }
+}
+
+impl DeltaTime for Ufo {
+    fn update(&mut self) -> usize {
+        Self::update_dt(&mut self.dt, ONE_FRAME)
+    }
+}
+
+/// Procedurally generate the UFO sprite.
+fn make_sprite() -> Sprite {
+    let width = SPRITE[0].len();
+    let height = SPRITE.len();
+    let pixels = SPRITE
+        .iter()
+        .flat_map(|row| row.chars())
+        .flat_map(|c| if c == '#' { COLOR } else { [0, 0, 0, 255] })
+        .collect();
+
+    Sprite::from_pixels(width, height, pixels)
+}
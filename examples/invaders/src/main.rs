@@ -26,8 +26,6 @@ struct Game {
     gilrs: Gilrs,
     /// GamePad ID for the player.
     gamepad: Option<GamepadId>,
-    /// Game pause state.
-    paused: bool,
 }
 
 impl Game {
@@ -39,7 +37,6 @@ impl Game {
             input: WinitInputHelper::new(),
             gilrs: Gilrs::new().unwrap(), // XXX: Don't unwrap.
             gamepad: None,
-            paused: false,
         }
     }
 
@@ -61,8 +58,9 @@ impl Game {
             let mut left = self.input.key_held(VirtualKeyCode::Left);
             let mut right = self.input.key_held(VirtualKeyCode::Right);
             let mut fire = self.input.key_pressed(VirtualKeyCode::Space);
-            let mut pause = self.input.key_pressed(VirtualKeyCode::Pause)
-                | self.input.key_pressed(VirtualKeyCode::P);
+            // The world toggles the pause when the button is first pressed
+            let mut pause =
+                self.input.key_held(VirtualKeyCode::Pause) | self.input.key_held(VirtualKeyCode::P);
 
             // GamePad controls
             if let Some(id) = self.gamepad {
@@ -73,16 +71,10 @@ impl Game {
                 fire |= gamepad.button_data(Button::South).map_or(false, |button| {
                     button.is_pressed() && button.counter() == self.gilrs.counter()
                 });
-                pause |= gamepad.button_data(Button::Start).map_or(false, |button| {
-                    button.is_pressed() && button.counter() == self.gilrs.counter()
-                });
+                pause |= gamepad.is_pressed(Button::Start);
             }
             self.gilrs.inc();
 
-            if pause {
-                self.paused = !self.paused;
-            }
-
             let direction = if left {
                 Direction::Left
             } else if right {
@@ -91,7 +83,11 @@ impl Game {
                 Direction::Still
             };
 
-            Controls { direction, fire }
+            Controls {
+                direction,
+                fire,
+                pause,
+            }
         };
     }
 }
@@ -133,9 +129,7 @@ fn main() -> Result<(), Error> {
         0.1,
         move |g| {
             // Update the world
-            if !g.game.paused {
-                g.game.world.update(&g.game.controls);
-            }
+            g.game.world.update(&g.game.controls);
         },
         move |g| {
             // Drawing